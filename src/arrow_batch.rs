@@ -0,0 +1,126 @@
+//! Packing records into Apache Arrow record batches.
+
+use std::sync::Arc;
+
+use arrow::{
+    array::{
+        ArrayRef, BinaryArray, BooleanArray, Date32Array, Float64Array, Int32Array, StringArray,
+        TimestampSecondArray,
+    },
+    datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+
+use crate::{
+    columns::{self, Columns, Values, GEOMETRY},
+    FieldInfo, FieldType, Result,
+};
+
+/// A column per DBF field, then, if `with_geometry`, the geometry column.
+/// Every column is nullable, since any DBF value can be missing.
+pub(crate) fn schema(fields: &[FieldInfo], with_geometry: bool) -> Result<SchemaRef> {
+    columns::check_names(fields, with_geometry)?;
+    let mut columns: Vec<_> = fields
+        .iter()
+        .map(|field| Field::new(field.name.as_str(), data_type(field.field_type), true))
+        .collect();
+    if with_geometry {
+        columns.push(Field::new(GEOMETRY, DataType::Binary, true));
+    }
+    Ok(Arc::new(Schema::new(columns)))
+}
+
+fn data_type(field_type: FieldType) -> DataType {
+    match field_type {
+        FieldType::Character | FieldType::Memo => DataType::Utf8,
+        FieldType::Numeric | FieldType::Float | FieldType::Double | FieldType::Currency => {
+            DataType::Float64
+        }
+        FieldType::Integer => DataType::Int32,
+        FieldType::Logical => DataType::Boolean,
+        FieldType::Date => DataType::Date32,
+        FieldType::DateTime => DataType::Timestamp(TimeUnit::Second, None),
+    }
+}
+
+/// `columns` as a single batch laid out as `schema`, which has to have
+/// come from the same fields (and agree on the geometry column).
+pub(crate) fn batch(schema: &SchemaRef, columns: Columns) -> Result<RecordBatch> {
+    let mut arrays: Vec<ArrayRef> = columns.fields.into_iter().map(array).collect();
+    if let Some(geometries) = columns.geometries {
+        arrays.push(Arc::new(BinaryArray::from_iter(geometries)));
+    }
+    // without any columns, the batch can't tell how many rows it has
+    let options = RecordBatchOptions::new().with_row_count(Some(columns.len));
+    Ok(RecordBatch::try_new_with_options(
+        schema.clone(),
+        arrays,
+        &options,
+    )?)
+}
+
+fn array(values: Values) -> ArrayRef {
+    match values {
+        Values::Text(values) => Arc::new(StringArray::from(values)),
+        Values::Number(values) => Arc::new(Float64Array::from(values)),
+        Values::Integer(values) => Arc::new(Int32Array::from(values)),
+        Values::Logical(values) => Arc::new(BooleanArray::from(values)),
+        Values::Date(values) => Arc::new(Date32Array::from(values)),
+        Values::DateTime(values) => Arc::new(TimestampSecondArray::from(values)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::Array;
+
+    use super::*;
+
+    #[test]
+    fn arrays() {
+        let fields = [FieldInfo {
+            name: "WHEN".to_owned(),
+            field_type: FieldType::DateTime,
+            length: 8,
+            decimal_count: 0,
+        }];
+        let schema = schema(&fields, true).unwrap();
+        assert_eq!(
+            schema.field(0).data_type(),
+            &DataType::Timestamp(TimeUnit::Second, None)
+        );
+        assert_eq!(schema.field(1).name(), GEOMETRY);
+
+        let columns = Columns {
+            len: 2,
+            fields: vec![Values::DateTime(vec![Some(86_400), None])],
+            geometries: Some(vec![None, Some(vec![1, 2])]),
+        };
+        let batch = batch(&schema, columns).unwrap();
+        let times = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<TimestampSecondArray>()
+            .unwrap();
+        assert_eq!(times.value(0), 86_400);
+        assert!(times.is_null(1));
+        let geometries = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<BinaryArray>()
+            .unwrap();
+        assert!(geometries.is_null(0));
+        assert_eq!(geometries.value(1), [1, 2]);
+    }
+
+    #[test]
+    fn without_columns() {
+        let columns = Columns {
+            len: 2,
+            fields: vec![],
+            geometries: None,
+        };
+        let batch = batch(&schema(&[], false).unwrap(), columns).unwrap();
+        assert_eq!((batch.num_rows(), batch.num_columns()), (2, 0));
+    }
+}
@@ -0,0 +1,191 @@
+//! Opening zipped shapefiles with something other than the defaults.
+
+use std::{
+    fs::File,
+    io::{Cursor, Read, Seek},
+    ops::BitOr,
+    path::Path,
+};
+
+use crate::{
+    container::open_zip, EncryptedZip, EncryptedZippedShapefile, Result, Shapefile, ZippedShapefile,
+};
+
+/// The knobs that have to be set before the container is first looked at.
+#[derive(Clone, Debug)]
+pub(crate) struct Options {
+    pub(crate) max_member_size: Option<u64>,
+    pub(crate) max_compression_ratio: Option<f64>,
+    pub(crate) encoding: Option<String>,
+    pub(crate) prj_lossy: bool,
+    pub(crate) required: Components,
+    pub(crate) strict: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            max_member_size: None,
+            max_compression_ratio: None,
+            encoding: None,
+            prj_lossy: true,
+            required: Components::NONE,
+            strict: false,
+        }
+    }
+}
+
+/// A set of the optional shapefile components, which can be combined with
+/// `|`, as in `Components::SHX | Components::PRJ`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Components(u8);
+
+impl Components {
+    pub const NONE: Self = Self(0);
+    pub const SHX: Self = Self(1);
+    pub const DBF: Self = Self(1 << 1);
+    pub const PRJ: Self = Self(1 << 2);
+
+    /// Whether every component in `other` is in `self` too.
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Components {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+/// Opens zipped shapefiles like [`ZippedShapefile::new`] and
+/// [`ZippedShapefile::open`] do, but configured first. Left alone, every
+/// option behaves just as it does for those.
+///
+/// Setting a `password` makes the builder produce an
+/// [`EncryptedZippedShapefile`] instead.
+#[derive(Clone, Debug, Default)]
+pub struct ZippedShapefileBuilder<P = ()> {
+    options: Options,
+    password: P,
+}
+
+impl<P> ZippedShapefileBuilder<P> {
+    /// Caps the size of the members that get read, as
+    /// [`Shapefile::set_max_member_size`] does, but from the start, so that
+    /// the `.cpg` is covered too.
+    pub fn max_member_size(mut self, bytes: u64) -> Self {
+        self.options.max_member_size = Some(bytes);
+        self
+    }
+
+    /// Rejects members that decompress to more than `ratio` times their
+    /// compressed size, as [`Shapefile::set_max_compression_ratio`] does,
+    /// but from the start.
+    pub fn max_compression_ratio(mut self, ratio: f64) -> Self {
+        self.options.max_compression_ratio = Some(ratio);
+        self
+    }
+
+    /// Decodes the DBF as `label` (a `.cpg` label, like `UTF-8` or
+    /// `CP1252`), whatever the `.cpg` or the DBF header say.
+    pub fn encoding_override<S: Into<String>>(mut self, label: S) -> Self {
+        self.options.encoding = Some(label.into());
+        self
+    }
+
+    /// Whether a `.prj` that isn't UTF-8 is decoded lossily (the default)
+    /// or fails with `Error::NonUtf8Projection` once it's read.
+    pub fn prj_lossy(mut self, lossy: bool) -> Self {
+        self.options.prj_lossy = lossy;
+        self
+    }
+
+    /// Refuses shapefiles lacking any of `components` (only the `.shp` is
+    /// required by default), failing with `Error::MissingShx`,
+    /// `Error::MissingDbf` or `Error::MissingProjection`. Every layer has to
+    /// have them, not just the default one; a lone `.prj` shared by several
+    /// layers counts for each of them.
+    pub fn require_components(mut self, components: Components) -> Self {
+        self.options.required = components;
+        self
+    }
+
+    /// Rejects archives with a `.shx`, `.dbf`, memo file, `.prj` or `.cpg`
+    /// whose stem isn't that of any `.shp` (like `b.dbf` next to `a.shp`),
+    /// failing with `Error::UnmatchedComponent` instead of ignoring it.
+    pub fn strict(mut self) -> Self {
+        self.options.strict = true;
+        self
+    }
+}
+
+impl ZippedShapefileBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decrypts the archive's members with `password`.
+    pub fn password<S: Into<Vec<u8>>>(self, password: S) -> ZippedShapefileBuilder<Vec<u8>> {
+        ZippedShapefileBuilder {
+            options: self.options,
+            password: password.into(),
+        }
+    }
+
+    pub fn build<R>(self, source: R) -> Result<ZippedShapefile<R>>
+    where
+        R: Read + Seek,
+    {
+        Shapefile::with_members(open_zip(source)?, None, self.options)
+    }
+
+    pub fn open<Q>(self, path: Q) -> Result<ZippedShapefile<File>>
+    where
+        Q: AsRef<Path>,
+    {
+        self.build(File::open(path)?)
+    }
+
+    /// Reads the whole archive from a stream that can't seek, like
+    /// [`ZippedShapefile::from_reader`]. The member size cap is checked
+    /// once it's in memory.
+    pub fn from_reader<R>(self, reader: R) -> Result<ZippedShapefile<Cursor<Vec<u8>>>>
+    where
+        R: Read,
+    {
+        self.build(Cursor::new(read_all(reader)?))
+    }
+}
+
+impl ZippedShapefileBuilder<Vec<u8>> {
+    pub fn build<R>(self, source: R) -> Result<EncryptedZippedShapefile<R>>
+    where
+        R: Read + Seek,
+    {
+        let container = EncryptedZip::new(open_zip(source)?, self.password);
+        Shapefile::with_members(container, None, self.options)
+    }
+
+    pub fn open<Q>(self, path: Q) -> Result<EncryptedZippedShapefile<File>>
+    where
+        Q: AsRef<Path>,
+    {
+        self.build(File::open(path)?)
+    }
+
+    pub fn from_reader<R>(self, reader: R) -> Result<EncryptedZippedShapefile<Cursor<Vec<u8>>>>
+    where
+        R: Read,
+    {
+        self.build(Cursor::new(read_all(reader)?))
+    }
+}
+
+fn read_all<R: Read>(mut reader: R) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    Ok(data)
+}
@@ -0,0 +1,130 @@
+//! Keeping decompressed members around so each only gets read once.
+
+use std::{collections::HashMap, sync::Arc};
+
+/// The members read so far, optionally within a byte budget that evicts
+/// the least recently used ones to make room.
+#[derive(Debug, Default)]
+pub(crate) struct Cache {
+    /// Each member's contents along with when it was last used.
+    members: HashMap<String, (Arc<[u8]>, u64)>,
+    capacity: Option<usize>,
+    bytes: usize,
+    /// Counts uses, standing in for a clock.
+    uses: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Cache {
+    pub(crate) fn get(&mut self, name: &str) -> Option<Arc<[u8]>> {
+        self.uses += 1;
+        match self.members.get_mut(name) {
+            Some((contents, last_used)) => {
+                self.hits += 1;
+                *last_used = self.uses;
+                Some(contents.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Keeps `contents` in place of whatever was kept under `name`, unless
+    /// it wouldn't fit in the budget even alone, in which case nothing is
+    /// kept under `name` at all.
+    pub(crate) fn insert(&mut self, name: &str, contents: Arc<[u8]>) {
+        if let Some((old, _)) = self.members.remove(name) {
+            self.bytes -= old.len();
+        }
+        if self
+            .capacity
+            .map_or(false, |capacity| contents.len() > capacity)
+        {
+            return;
+        }
+        self.bytes += contents.len();
+        self.members.insert(name.to_owned(), (contents, self.uses));
+        self.evict();
+    }
+
+    pub(crate) fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = Some(capacity);
+        self.evict();
+    }
+
+    /// How many lookups found their member, how many didn't, and how many
+    /// bytes are held.
+    pub(crate) fn stats(&self) -> (u64, u64, u64) {
+        (self.hits, self.misses, self.bytes as u64)
+    }
+
+    /// Drops every member, leaving the statistics alone.
+    pub(crate) fn clear(&mut self) {
+        self.members = HashMap::new();
+        self.bytes = 0;
+    }
+
+    fn evict(&mut self) {
+        let capacity = match self.capacity {
+            Some(capacity) => capacity,
+            None => return,
+        };
+        while self.bytes > capacity {
+            let oldest = self
+                .members
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(name, _)| name.clone());
+            match oldest.and_then(|name| self.members.remove(&name)) {
+                Some((contents, _)) => self.bytes -= contents.len(),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = Cache::default();
+        cache.set_capacity(10);
+        cache.insert("a", vec![0; 4].into());
+        cache.insert("b", vec![0; 4].into());
+        assert!(cache.get("a").is_some());
+        cache.insert("c", vec![0; 4].into());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+        assert_eq!(cache.stats(), (3, 1, 8));
+
+        cache.insert("big", vec![0; 11].into());
+        assert!(cache.get("big").is_none());
+        assert_eq!(cache.stats().2, 8);
+
+        cache.insert("a", vec![0; 6].into());
+        assert_eq!(cache.stats().2, 10);
+        cache.set_capacity(6);
+        assert!(cache.get("c").is_none());
+        assert_eq!(cache.stats().2, 6);
+    }
+
+    #[test]
+    fn too_big_replacement_drops_the_old_contents() {
+        let mut cache = Cache::default();
+        cache.set_capacity(10);
+        cache.insert("a", vec![1; 4].into());
+        cache.insert("a", vec![2; 11].into());
+        assert!(cache.get("a").is_none());
+        assert_eq!(cache.stats().2, 0);
+    }
+}
@@ -0,0 +1,213 @@
+//! Laying records out column by column, as the Arrow and Polars conversions
+//! both want them.
+
+use crate::{shape_geometry, wkb, Error, FieldInfo, FieldType, FieldValue, Record, Result, Shape};
+
+/// The name of the column holding each shape as WKB.
+pub(crate) const GEOMETRY: &str = "geometry";
+
+/// A record along with its shape, which is left out when the geometries
+/// aren't wanted.
+pub(crate) type Row = (Option<Shape>, Record);
+
+/// The values of one DBF field, as the type its column gets. Values of
+/// some other type than the field's count as missing.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Values {
+    /// `Character` and `Memo` fields.
+    Text(Vec<Option<String>>),
+    /// `Numeric`, `Float`, `Double` and `Currency` fields.
+    Number(Vec<Option<f64>>),
+    Integer(Vec<Option<i32>>),
+    Logical(Vec<Option<bool>>),
+    /// Days since the Unix epoch.
+    Date(Vec<Option<i32>>),
+    /// Seconds since the Unix epoch.
+    DateTime(Vec<Option<i64>>),
+}
+
+/// A batch of rows, a column at a time.
+pub(crate) struct Columns {
+    pub(crate) len: usize,
+    /// The values of each field, in order.
+    pub(crate) fields: Vec<Values>,
+    /// Each shape as WKB, `None` for null shapes; only there when the
+    /// geometries were asked for.
+    pub(crate) geometries: Option<Vec<Option<Vec<u8>>>>,
+}
+
+/// Fails with `ReservedColumnName` when one of `fields` would clash with
+/// the geometry column.
+pub(crate) fn check_names(fields: &[FieldInfo], with_geometry: bool) -> Result<()> {
+    match fields.iter().find(|field| field.name == GEOMETRY) {
+        Some(field) if with_geometry => Err(Error::ReservedColumnName(field.name.clone())),
+        _ => Ok(()),
+    }
+}
+
+impl Columns {
+    /// `rows` split up into a column per field in `fields`, then the
+    /// geometries `if with_geometry`; missing shapes are null there.
+    pub(crate) fn new(fields: &[FieldInfo], rows: Vec<Row>, with_geometry: bool) -> Result<Self> {
+        let len = rows.len();
+        let mut shapes = Vec::with_capacity(len);
+        let mut records = Vec::with_capacity(len);
+        for (shape, record) in rows {
+            shapes.push(shape);
+            records.push(record);
+        }
+
+        let fields = fields
+            .iter()
+            .map(|field| {
+                let values = records.iter_mut().map(|record| record.remove(&field.name));
+                values_of(field.field_type, values)
+            })
+            .collect();
+        let geometries = if with_geometry {
+            let geometries = shapes
+                .into_iter()
+                .map(|shape| match shape {
+                    None | Some(Shape::NullShape) => Ok(None),
+                    Some(shape) => {
+                        shape_geometry(shape).map(|geometry| Some(wkb::geometry(&geometry)))
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Some(geometries)
+        } else {
+            None
+        };
+        Ok(Self {
+            len,
+            fields,
+            geometries,
+        })
+    }
+}
+
+fn values_of<I>(field_type: FieldType, values: I) -> Values
+where
+    I: Iterator<Item = Option<FieldValue>>,
+{
+    match field_type {
+        FieldType::Character | FieldType::Memo => Values::Text(
+            values
+                .map(|value| match value {
+                    Some(FieldValue::Character(text)) => text,
+                    Some(FieldValue::Memo(text)) => Some(text),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        FieldType::Numeric | FieldType::Float | FieldType::Double | FieldType::Currency => {
+            Values::Number(
+                values
+                    .map(|value| match value {
+                        Some(FieldValue::Numeric(number)) => number,
+                        Some(FieldValue::Float(number)) => number.map(f64::from),
+                        Some(FieldValue::Double(number)) | Some(FieldValue::Currency(number)) => {
+                            Some(number)
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+            )
+        }
+        FieldType::Integer => Values::Integer(
+            values
+                .map(|value| match value {
+                    Some(FieldValue::Integer(number)) => Some(number),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        FieldType::Logical => Values::Logical(
+            values
+                .map(|value| match value {
+                    Some(FieldValue::Logical(logical)) => logical,
+                    _ => None,
+                })
+                .collect(),
+        ),
+        FieldType::Date => Values::Date(
+            values
+                .map(|value| match value {
+                    Some(FieldValue::Date(date)) => date.map(|date| date.to_unix_days()),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        FieldType::DateTime => Values::DateTime(
+            values
+                .map(|value| match value {
+                    Some(FieldValue::DateTime(datetime)) => Some(datetime.to_unix_timestamp()),
+                    _ => None,
+                })
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, field_type: FieldType) -> FieldInfo {
+        FieldInfo {
+            name: name.to_owned(),
+            field_type,
+            length: 8,
+            decimal_count: 0,
+        }
+    }
+
+    #[test]
+    fn split_into_columns() {
+        let fields = [
+            field("COUNT", FieldType::Numeric),
+            field("OPEN", FieldType::Logical),
+        ];
+        let mut record = Record::default();
+        record.insert("COUNT".to_owned(), FieldValue::Numeric(Some(3.0)));
+        // the wrong type for the field, which makes it missing
+        record.insert("OPEN".to_owned(), FieldValue::Integer(1));
+        let rows = || {
+            vec![
+                (
+                    Some(Shape::Point(shapefile::Point::new(1.0, 2.0))),
+                    record.clone(),
+                ),
+                (Some(Shape::NullShape), Record::default()),
+            ]
+        };
+
+        let columns = Columns::new(&fields, rows(), true).unwrap();
+        assert_eq!(columns.len, 2);
+        assert_eq!(
+            columns.fields,
+            [
+                Values::Number(vec![Some(3.0), None]),
+                Values::Logical(vec![None, None])
+            ]
+        );
+        let geometries = columns.geometries.unwrap();
+        assert_eq!(geometries[0].as_ref().unwrap()[..5], [1, 1, 0, 0, 0]);
+        assert_eq!(geometries[1], None);
+
+        assert!(Columns::new(&fields, rows(), false)
+            .unwrap()
+            .geometries
+            .is_none());
+    }
+
+    #[test]
+    fn reserved_names() {
+        let fields = [field(GEOMETRY, FieldType::Character)];
+        assert!(check_names(&fields, false).is_ok());
+        assert!(matches!(
+            check_names(&fields, true),
+            Err(Error::ReservedColumnName(name)) if name == GEOMETRY
+        ));
+    }
+}
@@ -0,0 +1,433 @@
+//! The places shapefile components can be read out of.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use zip::{
+    read::ZipFile,
+    result::{InvalidPassword, ZipError},
+    ZipArchive,
+};
+
+use crate::{split, Error, Result};
+
+/// Something holding named members, some of which make up a shapefile.
+///
+/// Member names are whatever the container calls them; components are found
+/// by looking at their extensions, so directories in names are fine.
+pub trait Container {
+    /// The names of every member.
+    fn member_names(&self) -> Result<Vec<String>>;
+
+    /// The full contents of the named member.
+    fn read_member(&mut self, name: &str) -> Result<Vec<u8>>;
+
+    /// The (uncompressed) size of the named member in bytes. Size caps are
+    /// checked with this before reading, and every component gets sized
+    /// when opening, so containers should be able to answer without
+    /// reading the member.
+    fn member_size(&mut self, name: &str) -> Result<u64> {
+        Ok(self.read_member(name)?.len() as u64)
+    }
+
+    /// The compressed size of the named member in bytes, or `None` for
+    /// containers that don't compress. Like `member_size()`, this shouldn't
+    /// need the member to be read.
+    fn member_compressed_size(&mut self, _name: &str) -> Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// The first `len` bytes of the named member, or all of it when it's
+    /// shorter than that.
+    fn read_member_head(&mut self, name: &str, len: u64) -> Result<Vec<u8>> {
+        let mut contents = self.read_member(name)?;
+        contents.truncate(len.try_into().unwrap_or(usize::MAX));
+        Ok(contents)
+    }
+
+    /// Writes the contents of the named member to `writer`, returning how
+    /// many bytes that was. Containers that can should do this without
+    /// holding the whole member in memory.
+    fn copy_member(&mut self, name: &str, writer: &mut dyn Write) -> Result<u64> {
+        let contents = self.read_member(name)?;
+        writer.write_all(&contents)?;
+        Ok(contents.len() as u64)
+    }
+}
+
+/// What `ZipArchive::new` fails with when the central directory isn't all
+/// on the last volume of a split archive.
+const MULTI_DISK: &str = "Support for multi-disk files is not implemented";
+
+/// Opens a zip archive, telling archives that are cut short or mangled
+/// (which no retry will fix) and split ones apart from other failures.
+pub(crate) fn open_zip<R>(source: R) -> Result<ZipArchive<R>>
+where
+    R: Read + Seek,
+{
+    let mut archive = ZipArchive::new(source).map_err(archive_error)?;
+    // the last volume of a split archive opens fine, since its central
+    // directory is all there, but the members it lists are really in the
+    // other volumes; so it's only worth looking for one when the first
+    // member isn't where the central directory says
+    if archive.is_empty() || archive.by_index_raw(0).is_ok() {
+        return Ok(archive);
+    }
+    let mut source = archive.into_inner();
+    if split::is_last_volume(&mut source)? {
+        return Err(Error::SplitArchiveUnsupported);
+    }
+    ZipArchive::new(source).map_err(archive_error)
+}
+
+fn archive_error(err: ZipError) -> Error {
+    match err {
+        ZipError::InvalidArchive(message) => Error::CorruptArchive(message.to_owned()),
+        ZipError::UnsupportedArchive(MULTI_DISK) => Error::SplitArchiveUnsupported,
+        ZipError::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            Error::CorruptArchive(err.to_string())
+        }
+        err => err.into(),
+    }
+}
+
+/// Opens the named member of `archive`, decrypting it with `password` if
+/// it's encrypted.
+fn zip_member<'a, R>(
+    archive: &'a mut ZipArchive<R>,
+    name: &str,
+    password: Option<&[u8]>,
+) -> Result<ZipFile<'a>>
+where
+    R: Read + Seek,
+{
+    let member = match password {
+        Some(password) => archive.by_name_decrypt(name, password),
+        None => archive.by_name(name).map(Ok),
+    };
+    match member {
+        Ok(Ok(member)) => Ok(member),
+        Ok(Err(InvalidPassword)) => Err(Error::InvalidPassword(name.to_owned())),
+        Err(ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED)) => {
+            Err(Error::PasswordRequired(name.to_owned()))
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// The uncompressed size the central directory gives the named member,
+/// which doesn't take the password even when it's encrypted.
+fn zip_member_size<R>(archive: &mut ZipArchive<R>, name: &str) -> Result<u64>
+where
+    R: Read + Seek,
+{
+    for index in 0..archive.len() {
+        let member = archive.by_index_raw(index)?;
+        if member.name() == name {
+            return Ok(member.size());
+        }
+    }
+    Err(ZipError::FileNotFound.into())
+}
+
+/// The most `read_zip_member()` allocates up front, whatever size the
+/// archive claims for the member; bigger ones grow as they're read.
+const PREALLOCATION_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Reads the whole of the named member, which has to hold exactly as many
+/// bytes as the archive says: more or fewer make it `CorruptArchive`, and
+/// no more than one byte past the declared size is ever read.
+fn read_zip_member<R>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    password: Option<&[u8]>,
+) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let zf = zip_member(archive, name, password)?;
+    let size: usize = zf
+        .size()
+        .try_into()
+        .map_err(|_| Error::MemberSizeTooLarge(zf.size()))?;
+    let mut buf = Vec::with_capacity(size.min(PREALLOCATION_LIMIT));
+    let read = zf.take(size as u64 + 1).read_to_end(&mut buf)?;
+    if read != size {
+        return Err(Error::CorruptArchive(format!(
+            "{} doesn't hold the {} bytes the archive says it does",
+            name, size
+        )));
+    }
+    Ok(buf)
+}
+
+fn read_zip_member_head<R>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    password: Option<&[u8]>,
+    len: u64,
+) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let mut head = Vec::new();
+    zip_member(archive, name, password)?
+        .take(len)
+        .read_to_end(&mut head)?;
+    Ok(head)
+}
+
+/// Directory entries (named with a trailing slash, as `ZipFile::is_dir()`
+/// has it) aren't members: they hold nothing, and a directory called
+/// `foo.shp/` shouldn't pass for a component.
+impl<R> Container for ZipArchive<R>
+where
+    R: Read + Seek,
+{
+    fn member_names(&self) -> Result<Vec<String>> {
+        Ok(self
+            .file_names()
+            .filter(|name| !name.ends_with(['/', '\\']))
+            .map(str::to_owned)
+            .collect())
+    }
+
+    fn read_member(&mut self, name: &str) -> Result<Vec<u8>> {
+        read_zip_member(self, name, None)
+    }
+
+    fn member_size(&mut self, name: &str) -> Result<u64> {
+        zip_member_size(self, name)
+    }
+
+    fn member_compressed_size(&mut self, name: &str) -> Result<Option<u64>> {
+        Ok(Some(zip_member(self, name, None)?.compressed_size()))
+    }
+
+    fn read_member_head(&mut self, name: &str, len: u64) -> Result<Vec<u8>> {
+        read_zip_member_head(self, name, None, len)
+    }
+
+    fn copy_member(&mut self, name: &str, writer: &mut dyn Write) -> Result<u64> {
+        Ok(io::copy(&mut zip_member(self, name, None)?, writer)?)
+    }
+}
+
+/// A zip archive whose members are encrypted with a password (either all of
+/// them or just some: unencrypted members are read as usual).
+///
+/// Only the traditional ZipCrypto scheme is supported, not AES.
+pub struct EncryptedZip<R> {
+    archive: ZipArchive<R>,
+    password: Vec<u8>,
+}
+
+impl<R> EncryptedZip<R>
+where
+    R: Read + Seek,
+{
+    pub fn new<P>(archive: ZipArchive<R>, password: P) -> Self
+    where
+        P: Into<Vec<u8>>,
+    {
+        Self {
+            archive,
+            password: password.into(),
+        }
+    }
+
+    pub fn into_archive(self) -> ZipArchive<R> {
+        self.archive
+    }
+}
+
+impl<R> Container for EncryptedZip<R>
+where
+    R: Read + Seek,
+{
+    fn member_names(&self) -> Result<Vec<String>> {
+        self.archive.member_names()
+    }
+
+    fn read_member(&mut self, name: &str) -> Result<Vec<u8>> {
+        read_zip_member(&mut self.archive, name, Some(&self.password))
+    }
+
+    fn member_size(&mut self, name: &str) -> Result<u64> {
+        zip_member_size(&mut self.archive, name)
+    }
+
+    fn member_compressed_size(&mut self, name: &str) -> Result<Option<u64>> {
+        let member = zip_member(&mut self.archive, name, Some(&self.password))?;
+        Ok(Some(member.compressed_size()))
+    }
+
+    fn read_member_head(&mut self, name: &str, len: u64) -> Result<Vec<u8>> {
+        read_zip_member_head(&mut self.archive, name, Some(&self.password), len)
+    }
+
+    fn copy_member(&mut self, name: &str, writer: &mut dyn Write) -> Result<u64> {
+        let mut member = zip_member(&mut self.archive, name, Some(&self.password))?;
+        Ok(io::copy(&mut member, writer)?)
+    }
+}
+
+/// A tar archive, optionally gzipped (as in `.tar.gz`/`.tgz`, which is
+/// recognized by its contents rather than its name).
+///
+/// Tar can't be read out of order, so the whole archive is read into memory
+/// (decompressed) up front; members' sizes come from their headers, so the
+/// size caps still apply before any member gets copied out. Only regular
+/// files whose names are valid UTF-8 are members, and when a name appears
+/// more than once the last one wins, as with `tar -x`.
+#[cfg(feature = "tar")]
+pub struct TarArchive {
+    data: Vec<u8>,
+    /// The name, offset into `data` and size of each member.
+    members: Vec<(String, usize, usize)>,
+}
+
+#[cfg(feature = "tar")]
+impl TarArchive {
+    pub fn new<R>(mut reader: R) -> Result<Self>
+    where
+        R: Read,
+    {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        if data.starts_with(&[0x1f, 0x8b]) {
+            let mut decompressed = Vec::new();
+            flate2::read::MultiGzDecoder::new(&data[..]).read_to_end(&mut decompressed)?;
+            data = decompressed;
+        }
+
+        let mut members: Vec<(String, usize, usize)> = Vec::new();
+        for entry in tar::Archive::new(&data[..]).entries()? {
+            let entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = match entry.path()?.to_str() {
+                Some(name) => name.to_owned(),
+                None => continue,
+            };
+            let offset = usize::try_from(entry.raw_file_position())
+                .map_err(|_| Error::MemberSizeTooLarge(entry.raw_file_position()))?;
+            let size = usize::try_from(entry.size())
+                .map_err(|_| Error::MemberSizeTooLarge(entry.size()))?;
+            if data.len() < offset.saturating_add(size) {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            match members.iter_mut().find(|(member, _, _)| *member == name) {
+                Some(member) => *member = (name, offset, size),
+                None => members.push((name, offset, size)),
+            }
+        }
+        Ok(Self { data, members })
+    }
+
+    fn member(&self, name: &str) -> Result<&[u8]> {
+        self.members
+            .iter()
+            .find(|(member, _, _)| member == name)
+            .map(|&(_, offset, size)| &self.data[offset..offset + size])
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound).into())
+    }
+}
+
+#[cfg(feature = "tar")]
+impl Container for TarArchive {
+    fn member_names(&self) -> Result<Vec<String>> {
+        Ok(self
+            .members
+            .iter()
+            .map(|(name, _, _)| name.clone())
+            .collect())
+    }
+
+    fn read_member(&mut self, name: &str) -> Result<Vec<u8>> {
+        Ok(self.member(name)?.to_vec())
+    }
+
+    fn member_size(&mut self, name: &str) -> Result<u64> {
+        Ok(self.member(name)?.len() as u64)
+    }
+
+    fn read_member_head(&mut self, name: &str, len: u64) -> Result<Vec<u8>> {
+        let member = self.member(name)?;
+        let len = usize::try_from(len).unwrap_or(usize::MAX).min(member.len());
+        Ok(member[..len].to_vec())
+    }
+
+    fn copy_member(&mut self, name: &str, writer: &mut dyn Write) -> Result<u64> {
+        let member = self.member(name)?;
+        writer.write_all(member)?;
+        Ok(member.len() as u64)
+    }
+}
+
+/// A directory of loose files, as left behind by unzipping a shapefile.
+///
+/// Only files directly inside the directory are considered, and only those
+/// whose names are valid UTF-8.
+#[derive(Clone, Debug)]
+pub struct Directory {
+    path: PathBuf,
+}
+
+impl Directory {
+    pub fn new<P>(path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            path: path.as_ref().to_owned(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Container for Directory {
+    fn member_names(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            let entry = entry?;
+            // `is_file` follows symlinks, which `DirEntry::file_type` doesn't
+            if !entry.path().is_file() {
+                continue;
+            }
+            if let Ok(name) = entry.file_name().into_string() {
+                names.push(name);
+            }
+        }
+        // `read_dir` order is up to the filesystem
+        names.sort();
+        Ok(names)
+    }
+
+    fn read_member(&mut self, name: &str) -> Result<Vec<u8>> {
+        Ok(fs::read(self.path.join(name))?)
+    }
+
+    fn member_size(&mut self, name: &str) -> Result<u64> {
+        Ok(fs::metadata(self.path.join(name))?.len())
+    }
+
+    fn read_member_head(&mut self, name: &str, len: u64) -> Result<Vec<u8>> {
+        let mut head = Vec::new();
+        File::open(self.path.join(name))?
+            .take(len)
+            .read_to_end(&mut head)?;
+        Ok(head)
+    }
+
+    fn copy_member(&mut self, name: &str, writer: &mut dyn Write) -> Result<u64> {
+        Ok(io::copy(&mut File::open(self.path.join(name))?, writer)?)
+    }
+}
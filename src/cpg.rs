@@ -0,0 +1,143 @@
+//! Codepages for decoding DBF text, as declared by `.cpg` files.
+
+use std::io::{Cursor, Read, Seek};
+
+use dbase::{encoding::LossyCodePage, yore::code_pages, UnicodeLossy};
+
+use crate::Buffer;
+
+/// The Windows codepage number named by the contents of a `.cpg` file.
+///
+/// Producers are inconsistent about how they spell these: `UTF-8`, `1252`,
+/// `CP1252`, `ANSI 1252` and `Windows-1252` all show up in the wild.
+pub(crate) fn codepage(label: &str) -> Option<u16> {
+    let label: String = label
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    match label.as_str() {
+        "UTF8" => return Some(65001),
+        // ISO-8859-1 only differs from windows-1252 in the C1 control range,
+        // which never appears in text (this is also what browsers do)
+        "ISO88591" | "88591" | "LATIN1" => return Some(1252),
+        _ => {}
+    }
+    let number = ["WINDOWS", "ANSI", "OEM", "IBM", "CP"]
+        .iter()
+        .find_map(|prefix| label.strip_prefix(prefix))
+        .unwrap_or(&label);
+    match number.parse().ok()? {
+        cp @ (437 | 850 | 852 | 861 | 865 | 866 | 874 | 1250..=1256 | 65001) => Some(cp),
+        _ => None,
+    }
+}
+
+/// The codepage named by a DBF's language driver ID, the header byte at
+/// offset 29. Only codepages we can decode are listed, which leaves out the
+/// CJK ones along with a handful of DOS ones like Greek and Turkish.
+pub(crate) fn ldid_codepage(ldid: u8) -> Option<u16> {
+    let codepage = match ldid {
+        0x01 | 0x09 | 0x0b | 0x0d | 0x0f | 0x11 | 0x15 | 0x18 | 0x19 | 0x1b => 437,
+        0x02 | 0x0a | 0x0e | 0x10 | 0x12 | 0x14 | 0x16 | 0x1a | 0x1d | 0x25 | 0x37 => 850,
+        0x1f | 0x22 | 0x23 | 0x40 | 0x64 | 0x87 => 852,
+        0x67 => 861,
+        0x08 | 0x17 | 0x66 => 865,
+        0x26 | 0x65 => 866,
+        0x50 | 0x7c => 874,
+        0xc8 => 1250,
+        0xc9 => 1251,
+        // 0x57 is ESRI's "whatever the system codepage is", which in
+        // practice means 1252
+        0x03 | 0x57 | 0x58 | 0x59 => 1252,
+        0xcb => 1253,
+        0xca => 1254,
+        0x7d => 1255,
+        0x7e => 1256,
+        _ => return None,
+    };
+    Some(codepage)
+}
+
+/// The language driver ID of a DBF, if it has a header at all.
+pub(crate) fn ldid(dbf: &[u8]) -> Option<u8> {
+    dbf.get(29).copied()
+}
+
+/// Opens a DBF (along with its memo file, if any) whose text is decoded
+/// using `codepage`, or failing that the codepage named by its language
+/// driver ID, or as (lossy) UTF-8 when neither is known.
+pub(crate) fn dbase_reader(
+    mut dbf: Buffer,
+    memo: Option<Buffer>,
+    codepage: Option<u16>,
+) -> Result<dbase::Reader<Buffer>, dbase::Error> {
+    let codepage = codepage.or_else(|| ldid(dbf.get_ref()).and_then(ldid_codepage));
+    // `dbase` refuses to open files whose language driver byte names a
+    // codepage it has no decoder for, even though we never use its choice;
+    // the contents are shared, so this takes a copy.
+    if let Some(0x68..=0x6b | 0x78..=0x7b) = ldid(dbf.get_ref()) {
+        let mut patched = dbf.get_ref().to_vec();
+        patched[29] = 0;
+        dbf = Cursor::new(patched.into());
+    }
+    // the builder's encoding type is moot since we set our own below
+    let mut builder = dbase::ReaderBuilder::<_, UnicodeLossy>::new(dbf);
+    if let Some(memo) = memo {
+        builder = builder.with_memo(memo);
+    }
+    let mut reader = builder.build()?;
+    set_encoding(&mut reader, codepage.unwrap_or(65001));
+    Ok(reader)
+}
+
+fn set_encoding<T: Read + Seek>(reader: &mut dbase::Reader<T>, codepage: u16) {
+    match codepage {
+        437 => reader.set_encoding(LossyCodePage(code_pages::CP437)),
+        850 => reader.set_encoding(LossyCodePage(code_pages::CP850)),
+        852 => reader.set_encoding(LossyCodePage(code_pages::CP852)),
+        861 => reader.set_encoding(LossyCodePage(code_pages::CP861)),
+        865 => reader.set_encoding(LossyCodePage(code_pages::CP865)),
+        866 => reader.set_encoding(LossyCodePage(code_pages::CP866)),
+        874 => reader.set_encoding(LossyCodePage(code_pages::CP874)),
+        1250 => reader.set_encoding(LossyCodePage(code_pages::CP1250)),
+        1251 => reader.set_encoding(LossyCodePage(code_pages::CP1251)),
+        1252 => reader.set_encoding(LossyCodePage(code_pages::CP1252)),
+        1253 => reader.set_encoding(LossyCodePage(code_pages::CP1253)),
+        1254 => reader.set_encoding(LossyCodePage(code_pages::CP1254)),
+        1255 => reader.set_encoding(LossyCodePage(code_pages::CP1255)),
+        1256 => reader.set_encoding(LossyCodePage(code_pages::CP1256)),
+        _ => reader.set_encoding(UnicodeLossy),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels() {
+        assert_eq!(codepage("UTF-8"), Some(65001));
+        assert_eq!(codepage("utf8\r\n"), Some(65001));
+        assert_eq!(codepage("ISO-8859-1"), Some(1252));
+        assert_eq!(codepage("1252"), Some(1252));
+        assert_eq!(codepage("ANSI 1251"), Some(1251));
+        assert_eq!(codepage("Windows-1250"), Some(1250));
+        assert_eq!(codepage("CP865"), Some(865));
+        assert_eq!(codepage("OEM 437"), Some(437));
+        assert_eq!(codepage("Big5"), None);
+        assert_eq!(codepage("1234"), None);
+    }
+
+    #[test]
+    fn ldids() {
+        assert_eq!(ldid_codepage(0x03), Some(1252));
+        assert_eq!(ldid_codepage(0x57), Some(1252));
+        assert_eq!(ldid_codepage(0x65), Some(866));
+        assert_eq!(ldid_codepage(0x66), Some(865));
+        assert_eq!(ldid_codepage(0xc9), Some(1251));
+        // Shift-JIS, which we can't decode
+        assert_eq!(ldid_codepage(0x13), None);
+        assert_eq!(ldid_codepage(0x00), None);
+    }
+}
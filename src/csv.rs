@@ -0,0 +1,95 @@
+//! Writing DBF records as CSV.
+
+use std::io::Write;
+
+use crate::{util, FieldValue, Record, Result};
+
+/// Writes a header row of `names`, then one row per record with the fields
+/// in the same order.
+pub(crate) fn write<W, I>(mut out: W, names: &[String], records: I) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = Result<Record>>,
+{
+    write_row(&mut out, names.iter().map(String::as_str))?;
+    for record in records {
+        let record = record?;
+        let fields: Vec<_> = names
+            .iter()
+            .map(|name| record.get(name).map(field_value).unwrap_or_default())
+            .collect();
+        write_row(&mut out, fields.iter().map(String::as_str))?;
+    }
+    Ok(())
+}
+
+/// One line of comma-separated fields, quoted as RFC 4180 requires.
+fn write_row<'a, W>(out: &mut W, fields: impl Iterator<Item = &'a str>) -> Result<()>
+where
+    W: Write,
+{
+    for (index, field) in fields.enumerate() {
+        if index > 0 {
+            out.write_all(b",")?;
+        }
+        if field.contains(&[',', '"', '\r', '\n'][..]) {
+            write!(out, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            out.write_all(field.as_bytes())?;
+        }
+    }
+    out.write_all(b"\r\n")?;
+    Ok(())
+}
+
+/// The text for a DBF value; missing values are left empty, and dates are
+/// ISO 8601, like `2023-01-31` or `2023-01-31T12:00:00`.
+pub(crate) fn field_value(value: &FieldValue) -> String {
+    match value {
+        FieldValue::Character(Some(text)) | FieldValue::Memo(text) => text.clone(),
+        FieldValue::Numeric(Some(number))
+        | FieldValue::Double(number)
+        | FieldValue::Currency(number) => number.to_string(),
+        FieldValue::Float(Some(number)) => number.to_string(),
+        FieldValue::Integer(number) => number.to_string(),
+        FieldValue::Logical(Some(logical)) => logical.to_string(),
+        FieldValue::Date(Some(date)) => util::iso_date(date),
+        FieldValue::DateTime(datetime) => util::iso_datetime(datetime),
+        FieldValue::Character(None)
+        | FieldValue::Numeric(None)
+        | FieldValue::Float(None)
+        | FieldValue::Logical(None)
+        | FieldValue::Date(None) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        let mut out = Vec::new();
+        write_row(
+            &mut out,
+            ["plain", "a,b", "say \"hi\"", "two\nlines", ""].into_iter(),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\r\n"
+        );
+    }
+
+    #[test]
+    fn field_values() {
+        assert_eq!(field_value(&FieldValue::Numeric(Some(12.5))), "12.5");
+        assert_eq!(field_value(&FieldValue::Numeric(Some(3.0))), "3");
+        assert_eq!(field_value(&FieldValue::Numeric(None)), "");
+        assert_eq!(field_value(&FieldValue::Logical(Some(false))), "false");
+        assert_eq!(
+            field_value(&FieldValue::Date(Some(dbase::Date::new(31, 1, 2023)))),
+            "2023-01-31"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Writing a layer into a GeoPackage, the SQLite-based format that QGIS and
+//! GDAL read natively.
+
+use std::path::Path;
+
+use rusqlite::{params, params_from_iter, types::Value, Connection};
+
+use crate::{
+    shape_bbox, shape_geometry, util, wkb, Error, FieldInfo, FieldType, FieldValue, Record, Result,
+    Shape,
+};
+
+/// `GPKG`, as the SQLite application ID.
+const APPLICATION_ID: i32 = 0x4750_4B47;
+/// Version 1.2 of the spec.
+const USER_VERSION: i32 = 10200;
+/// The spatial reference system the spec reserves for undefined cartesian
+/// coordinates.
+const UNDEFINED_SRS: i64 = -1;
+
+/// The tables every GeoPackage has, along with the reference systems the
+/// spec requires.
+const METADATA: &str = r#"
+CREATE TABLE IF NOT EXISTS gpkg_spatial_ref_sys (
+    srs_name TEXT NOT NULL,
+    srs_id INTEGER PRIMARY KEY,
+    organization TEXT NOT NULL,
+    organization_coordsys_id INTEGER NOT NULL,
+    definition TEXT NOT NULL,
+    description TEXT
+);
+CREATE TABLE IF NOT EXISTS gpkg_contents (
+    table_name TEXT NOT NULL PRIMARY KEY,
+    data_type TEXT NOT NULL,
+    identifier TEXT UNIQUE,
+    description TEXT DEFAULT '',
+    last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+    min_x DOUBLE,
+    min_y DOUBLE,
+    max_x DOUBLE,
+    max_y DOUBLE,
+    srs_id INTEGER,
+    CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+);
+CREATE TABLE IF NOT EXISTS gpkg_geometry_columns (
+    table_name TEXT NOT NULL,
+    column_name TEXT NOT NULL,
+    geometry_type_name TEXT NOT NULL,
+    srs_id INTEGER NOT NULL,
+    z TINYINT NOT NULL,
+    m TINYINT NOT NULL,
+    CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+    CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+    CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+);
+INSERT OR IGNORE INTO gpkg_spatial_ref_sys VALUES
+    ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined', NULL),
+    ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', NULL),
+    ('WGS 84 geodetic', 4326, 'EPSG', 4326,
+     'GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]]',
+     NULL);
+"#;
+
+/// What goes into the feature table besides the rows.
+pub(crate) struct Table<'a> {
+    pub(crate) name: &'a str,
+    /// The geometry type name for `gpkg_geometry_columns`, like `POINT`.
+    pub(crate) geometry_type: &'static str,
+    /// The EPSG code and WKT of the layer's CRS, if it has one.
+    pub(crate) srs: Option<(u32, &'a str)>,
+    pub(crate) extent: Option<[f64; 4]>,
+    pub(crate) fields: &'a [FieldInfo],
+}
+
+/// The columns the feature table has besides the fields; SQLite ignores
+/// case in column names.
+const RESERVED: [&str; 2] = ["fid", "geom"];
+
+/// Adds `table` to the GeoPackage at `path`, creating it if need be.
+pub(crate) fn write<P, I>(path: P, table: &Table, rows: I) -> Result<()>
+where
+    P: AsRef<Path>,
+    I: Iterator<Item = Result<(Shape, Record)>>,
+{
+    check_names(table.fields)?;
+    let mut connection = Connection::open(path)?;
+    connection.pragma_update(None, "application_id", APPLICATION_ID)?;
+    connection.pragma_update(None, "user_version", USER_VERSION)?;
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(METADATA)?;
+
+    let srs_id = match table.srs {
+        Some((code, wkt)) => {
+            transaction.execute(
+                "INSERT OR IGNORE INTO gpkg_spatial_ref_sys \
+                 (srs_name, srs_id, organization, organization_coordsys_id, definition) \
+                 VALUES (?1, ?2, 'EPSG', ?2, ?3)",
+                params![format!("EPSG:{}", code), code, wkt],
+            )?;
+            i64::from(code)
+        }
+        None => UNDEFINED_SRS,
+    };
+
+    let mut columns = vec![
+        "fid INTEGER PRIMARY KEY AUTOINCREMENT".to_owned(),
+        format!("geom {}", table.geometry_type),
+    ];
+    columns.extend(
+        table
+            .fields
+            .iter()
+            .map(|field| format!("{} {}", quote(&field.name), column_type(field))),
+    );
+    transaction.execute_batch(&format!(
+        "CREATE TABLE {} ({})",
+        quote(table.name),
+        columns.join(", ")
+    ))?;
+
+    let [min_x, min_y, max_x, max_y] = match table.extent {
+        Some([min_x, min_y, max_x, max_y]) => [Some(min_x), Some(min_y), Some(max_x), Some(max_y)],
+        None => [None; 4],
+    };
+    transaction.execute(
+        "INSERT INTO gpkg_contents \
+         (table_name, data_type, identifier, min_x, min_y, max_x, max_y, srs_id) \
+         VALUES (?1, 'features', ?1, ?2, ?3, ?4, ?5, ?6)",
+        params![table.name, min_x, min_y, max_x, max_y, srs_id],
+    )?;
+    transaction.execute(
+        "INSERT INTO gpkg_geometry_columns VALUES (?1, 'geom', ?2, ?3, 0, 0)",
+        params![table.name, table.geometry_type, srs_id],
+    )?;
+
+    {
+        let mut names = vec!["geom".to_owned()];
+        names.extend(table.fields.iter().map(|field| quote(&field.name)));
+        let placeholders = vec!["?"; names.len()].join(", ");
+        let mut insert = transaction.prepare(&format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote(table.name),
+            names.join(", "),
+            placeholders
+        ))?;
+        for row in rows {
+            let (shape, mut record) = row?;
+            let mut values = vec![match geometry(shape, srs_id as i32)? {
+                Some(blob) => Value::Blob(blob),
+                None => Value::Null,
+            }];
+            values.extend(
+                table
+                    .fields
+                    .iter()
+                    .map(|field| value(record.remove(&field.name))),
+            );
+            insert.execute(params_from_iter(values))?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Fails with `ReservedColumnName` when a field would clash with `fid` or
+/// `geom`, before anything gets written.
+fn check_names(fields: &[FieldInfo]) -> Result<()> {
+    match fields.iter().find(|field| {
+        RESERVED
+            .iter()
+            .any(|reserved| field.name.eq_ignore_ascii_case(reserved))
+    }) {
+        Some(field) => Err(Error::ReservedColumnName(field.name.clone())),
+        None => Ok(()),
+    }
+}
+
+/// `name` as an SQL identifier.
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn column_type(field: &FieldInfo) -> &'static str {
+    match field.field_type {
+        FieldType::Character | FieldType::Memo => "TEXT",
+        FieldType::Numeric if field.decimal_count == 0 => "INTEGER",
+        FieldType::Numeric | FieldType::Double | FieldType::Currency => "DOUBLE",
+        FieldType::Float => "FLOAT",
+        FieldType::Integer => "MEDIUMINT",
+        FieldType::Logical => "BOOLEAN",
+        FieldType::Date => "DATE",
+        FieldType::DateTime => "DATETIME",
+    }
+}
+
+fn value(value: Option<FieldValue>) -> Value {
+    match value {
+        Some(FieldValue::Character(Some(text))) | Some(FieldValue::Memo(text)) => Value::Text(text),
+        Some(FieldValue::Numeric(Some(number)))
+        | Some(FieldValue::Double(number))
+        | Some(FieldValue::Currency(number)) => Value::Real(number),
+        Some(FieldValue::Float(Some(number))) => Value::Real(f64::from(number)),
+        Some(FieldValue::Integer(number)) => Value::Integer(i64::from(number)),
+        Some(FieldValue::Logical(Some(logical))) => Value::Integer(i64::from(logical)),
+        Some(FieldValue::Date(Some(date))) => Value::Text(util::iso_date(&date)),
+        // the spec wants milliseconds and a time zone, which DBF doesn't keep
+        Some(FieldValue::DateTime(datetime)) => {
+            Value::Text(format!("{}.000Z", util::iso_datetime(&datetime)))
+        }
+        _ => Value::Null,
+    }
+}
+
+/// A GeoPackage geometry blob: a header naming the SRS and holding the
+/// envelope, then the WKB. `None` for null shapes.
+fn geometry(shape: Shape, srs_id: i32) -> Result<Option<Vec<u8>>> {
+    let envelope = match shape_bbox(&shape) {
+        Some(envelope) => envelope,
+        None => return Ok(None),
+    };
+    let geometry = shape_geometry(shape)?;
+    let mut blob = b"GP".to_vec();
+    // version 1, then little-endian with an `[min_x, max_x, min_y, max_y]`
+    // envelope
+    blob.extend_from_slice(&[0, 0b0000_0011]);
+    blob.extend_from_slice(&srs_id.to_le_bytes());
+    let [min_x, min_y, max_x, max_y] = envelope;
+    for bound in [min_x, max_x, min_y, max_y] {
+        blob.extend_from_slice(&bound.to_le_bytes());
+    }
+    blob.extend_from_slice(&wkb::geometry(&geometry));
+    Ok(Some(blob))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometry_header() {
+        let blob = geometry(Shape::Point(shapefile::Point::new(1.0, 2.0)), 4326)
+            .unwrap()
+            .unwrap();
+        assert_eq!(&blob[..4], b"GP\x00\x03");
+        assert_eq!(&blob[4..8], &4326i32.to_le_bytes());
+        assert_eq!(&blob[8..16], &1.0f64.to_le_bytes());
+        assert_eq!(&blob[24..32], &2.0f64.to_le_bytes());
+        assert_eq!(blob.len(), 40 + 21);
+        assert!(geometry(Shape::NullShape, 4326).unwrap().is_none());
+    }
+
+    #[test]
+    fn reserved_names() {
+        let field = |name: &str| FieldInfo {
+            name: name.to_owned(),
+            field_type: FieldType::Character,
+            length: 8,
+            decimal_count: 0,
+        };
+        assert!(check_names(&[field("NAME"), field("GEOMETRY")]).is_ok());
+        assert!(matches!(
+            check_names(&[field("NAME"), field("FID")]),
+            Err(Error::ReservedColumnName(name)) if name == "FID"
+        ));
+        assert!(check_names(&[field("geom")]).is_err());
+    }
+
+    #[test]
+    fn quoting() {
+        assert_eq!(quote("NAME"), "\"NAME\"");
+        assert_eq!(quote("a\"b"), "\"a\"\"b\"");
+    }
+}
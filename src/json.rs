@@ -0,0 +1,109 @@
+//! Converting records (and, with the `geojson` feature, shapes) to JSON.
+
+#[cfg(feature = "geojson")]
+use geojson::{Feature, Geometry, GeometryValue};
+#[cfg(any(feature = "geojson", feature = "serde"))]
+use serde_json::Map;
+use serde_json::Value as JsonValue;
+
+#[cfg(any(feature = "geojson", feature = "serde"))]
+use crate::Record;
+#[cfg(feature = "geojson")]
+use crate::{shape_geometry, Result, Shape};
+use crate::{util, FieldValue};
+
+/// A GeoJSON feature for one shape and its record. Null shapes get a `null`
+/// geometry rather than being an error, since GeoJSON has room for them.
+#[cfg(feature = "geojson")]
+pub(crate) fn feature(shape: Shape, record: Record) -> Result<Feature> {
+    let geometry = match shape {
+        Shape::NullShape => None,
+        shape => {
+            let geometry = shape_geometry(shape)?;
+            Some(Geometry::new(GeometryValue::from(&geometry)))
+        }
+    };
+    Ok(Feature {
+        geometry,
+        properties: Some(record_to_json(record)),
+        ..Feature::default()
+    })
+}
+
+/// A JSON object with a member for each field of `record`.
+#[cfg(any(feature = "geojson", feature = "serde"))]
+pub(crate) fn record_to_json(record: Record) -> Map<String, JsonValue> {
+    record
+        .into_iter()
+        .map(|(name, value)| (name, field_value_to_json(&value)))
+        .collect()
+}
+
+/// The JSON equivalent of a DBF value; missing values (and non-finite
+/// numbers, which JSON can't express) become `null`, and dates become
+/// ISO 8601 strings like `2023-01-31` or `2023-01-31T12:00:00`.
+pub fn field_value_to_json(value: &FieldValue) -> JsonValue {
+    match *value {
+        FieldValue::Character(Some(ref text)) | FieldValue::Memo(ref text) => text.as_str().into(),
+        FieldValue::Numeric(Some(number))
+        | FieldValue::Double(number)
+        | FieldValue::Currency(number) => number.into(),
+        FieldValue::Float(Some(number)) => f64::from(number).into(),
+        FieldValue::Integer(number) => number.into(),
+        FieldValue::Logical(Some(logical)) => logical.into(),
+        FieldValue::Date(Some(ref date)) => util::iso_date(date).into(),
+        FieldValue::DateTime(ref datetime) => util::iso_datetime(datetime).into(),
+        FieldValue::Character(None)
+        | FieldValue::Numeric(None)
+        | FieldValue::Float(None)
+        | FieldValue::Logical(None)
+        | FieldValue::Date(None) => JsonValue::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_values() {
+        assert_eq!(
+            field_value_to_json(&FieldValue::Character(Some("x".to_owned()))),
+            JsonValue::from("x")
+        );
+        assert_eq!(
+            field_value_to_json(&FieldValue::Character(None)),
+            JsonValue::Null
+        );
+        assert_eq!(
+            field_value_to_json(&FieldValue::Numeric(Some(1.5))),
+            JsonValue::from(1.5)
+        );
+        assert_eq!(
+            field_value_to_json(&FieldValue::Numeric(None)),
+            JsonValue::Null
+        );
+        assert_eq!(
+            field_value_to_json(&FieldValue::Double(f64::NAN)),
+            JsonValue::Null
+        );
+        assert_eq!(
+            field_value_to_json(&FieldValue::Integer(7)),
+            JsonValue::from(7)
+        );
+        assert_eq!(
+            field_value_to_json(&FieldValue::Logical(Some(true))),
+            JsonValue::from(true)
+        );
+        assert_eq!(
+            field_value_to_json(&FieldValue::Date(Some(dbase::Date::new(31, 1, 2023)))),
+            JsonValue::from("2023-01-31")
+        );
+        let datetime =
+            dbase::DateTime::new(dbase::Date::new(2, 3, 2001), dbase::Time::new(4, 5, 6));
+        assert_eq!(
+            field_value_to_json(&FieldValue::DateTime(datetime)),
+            JsonValue::from("2001-03-02T04:05:06")
+        );
+    }
+}
@@ -0,0 +1,81 @@
+//! A layer read out of its container in full.
+
+use std::ops::Index;
+
+use crate::{FieldInfo, Record, Shape};
+
+/// Every shape and record of a layer, along with its `.prj` and DBF
+/// schema, as returned by `Shapefile::load()`. Nothing is borrowed from the
+/// container, so it can be dropped (or the source reader closed) while the
+/// layer lives on.
+pub struct Layer {
+    features: Vec<(Shape, Record)>,
+    projection: Option<String>,
+    schema: Option<Vec<FieldInfo>>,
+}
+
+impl Layer {
+    pub(crate) fn new(
+        features: Vec<(Shape, Record)>,
+        projection: Option<String>,
+        schema: Option<Vec<FieldInfo>>,
+    ) -> Self {
+        Self {
+            features,
+            projection,
+            schema,
+        }
+    }
+
+    /// How many shapes (and records) there are.
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    /// The shape and record at `index`, or `None` past the end.
+    pub fn get(&self, index: usize) -> Option<&(Shape, Record)> {
+        self.features.get(index)
+    }
+
+    /// Every shape along with its record, in order; records are empty when
+    /// there was no `.dbf`.
+    pub fn iter(&self) -> std::slice::Iter<'_, (Shape, Record)> {
+        self.features.iter()
+    }
+
+    /// The text of the `.prj`, as `Shapefile::projection()` had it.
+    pub fn projection(&self) -> Option<&str> {
+        self.projection.as_deref()
+    }
+
+    /// The DBF's fields, or `None` when there was no `.dbf`.
+    pub fn schema(&self) -> Option<&[FieldInfo]> {
+        self.schema.as_deref()
+    }
+
+    /// The shapes and records, giving up the rest.
+    pub fn into_features(self) -> Vec<(Shape, Record)> {
+        self.features
+    }
+}
+
+impl Index<usize> for Layer {
+    type Output = (Shape, Record);
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.features[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a Layer {
+    type Item = &'a (Shape, Record);
+    type IntoIter = std::slice::Iter<'a, (Shape, Record)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -1,33 +1,131 @@
 use std::{
-    fs::File,
-    io::{Cursor, Read, Seek},
-    path::Path,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt,
+    fs::{self, File},
+    io::{self, Cursor, Read, Seek, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
-pub use shapefile::{dbase::FieldValue, reader::ShapeRecordIterator, Reader, Shape, ShapeReader};
+pub use shapefile::{
+    dbase::{FieldType, FieldValue, Record},
+    reader::ShapeRecordIterator,
+    record::BBoxZ,
+    Reader, Shape, ShapeReader,
+};
+use shapefile::{
+    header::Header,
+    record::{traits::HasXY, GenericBBox},
+};
+#[cfg(feature = "streaming")]
+pub use tempfile::SpooledTempFile;
 use thiserror::Error;
 use zip::ZipArchive;
 
-// FIXME: optional geo-types feature
+#[cfg(feature = "arrow")]
+pub use arrow;
+#[cfg(feature = "geo")]
+pub use geo;
+#[cfg(feature = "geo-types")]
+pub use geo_types;
+#[cfg(feature = "geojson")]
+pub use geojson;
+#[cfg(feature = "polars")]
+pub use polars;
+#[cfg(feature = "rstar")]
+pub use rstar;
+#[cfg(feature = "serde")]
+pub use serde;
+#[cfg(feature = "json")]
+pub use serde_json;
+
+#[cfg(feature = "arrow")]
+mod arrow_batch;
+mod builder;
+mod cache;
+#[cfg(any(feature = "arrow", feature = "polars"))]
+mod columns;
+mod container;
+mod cpg;
+mod csv;
+#[cfg(feature = "geopackage")]
+mod geopackage;
+#[cfg(feature = "json")]
+mod json;
+mod layer;
+#[cfg(feature = "geo")]
+mod measure;
+#[cfg(feature = "polars")]
+mod polars_frame;
+mod prj;
+mod records;
+#[cfg(feature = "proj")]
+mod reproject;
+#[cfg(feature = "rstar")]
+mod rtree;
+mod schema;
+mod shx;
+mod split;
+pub mod util;
+#[cfg(feature = "geo")]
+mod validate;
+#[cfg(feature = "geo")]
+mod winding;
+#[cfg(feature = "wkb")]
+mod wkb;
+#[cfg(feature = "wkt")]
+mod wkt;
+mod writer;
+
+use builder::Options;
+pub use builder::{Components, ZippedShapefileBuilder};
+use cache::Cache;
+#[cfg(any(feature = "arrow", feature = "polars"))]
+use columns::Columns;
+#[cfg(feature = "tar")]
+pub use container::TarArchive;
+pub use container::{Container, Directory, EncryptedZip};
+#[cfg(feature = "json")]
+pub use json::field_value_to_json;
+pub use layer::Layer;
+pub use prj::Crs;
+#[cfg(feature = "rstar")]
+pub use rtree::IndexedGeometry;
+pub use schema::FieldInfo;
+#[cfg(feature = "geo")]
+pub use validate::{GeometryIssue, GeometryIssueKind};
+pub use writer::ZippedShapefileWriter;
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("IO Error")]
     IOError(#[from] std::io::Error),
 
+    #[error("Couldn't read {name}: {source}")]
+    MemberRead { name: String, source: Box<Error> },
+
     #[error("DBase Error")]
     DBase(#[from] dbase::Error),
 
     #[error("Zipfile Error")]
     Zip(#[from] zip::result::ZipError),
 
+    #[error("Corrupt or truncated zip archive: {0}")]
+    CorruptArchive(String),
+
+    #[error(
+        "Split zip archives have to be opened with `ZippedShapefile::open_volumes` \
+         (or joined with `zip -s 0` first), and zip64 ones can't be joined"
+    )]
+    SplitArchiveUnsupported,
+
     #[error("Shapefile Error")]
     Shapefile(#[from] shapefile::Error),
 
     #[error("Multiple files found with extension {0}")]
     MultipleFilesFound(&'static str),
 
-    #[error("No .shp file found in zipfile")]
+    #[error("No .shp file found")]
     NoShpFound,
 
     #[error("unknown")]
@@ -36,20 +134,573 @@ pub enum Error {
     #[error("zip member size larger than `usize`")]
     MemberSizeTooLarge(u64),
 
-    #[error("No .dfb file found in zipfile")]
+    #[error("{name} decompresses to {ratio:.0} times its size")]
+    SuspiciousCompressionRatio { name: String, ratio: f64 },
+
+    #[error("No .dbf file found")]
     NoDbfFound,
+
+    #[error("No .shx file found for layer {0}")]
+    MissingShx(String),
+
+    #[error("No .dbf file found for layer {0}")]
+    MissingDbf(String),
+
+    #[error("No .prj file found for layer {0}")]
+    MissingProjection(String),
+
+    #[error("{0} doesn't share its stem with any .shp")]
+    UnmatchedComponent(String),
+
+    #[error("No member named {0}")]
+    MemberNotFound(String),
+
+    #[cfg(feature = "glob")]
+    #[error("Invalid layer pattern")]
+    InvalidPattern(#[from] glob::PatternError),
+
+    #[error("No layer named {0}")]
+    LayerNotFound(String),
+
+    #[error("The .shp is only {0} bytes, too short for its 100-byte header")]
+    ShpHeaderTooShort(usize),
+
+    #[error(".shx size {0} isn't a 100-byte header plus 8-byte records")]
+    InvalidShxSize(u64),
+
+    #[error("The .shp holds {shp} shapes but the .dbf {dbf} records")]
+    RecordCountMismatch { shp: usize, dbf: usize },
+
+    #[error(".prj isn't valid UTF-8")]
+    NonUtf8Projection,
+
+    #[error("No field named {0}")]
+    FieldNotFound(String),
+
+    #[error("Field {0} isn't numeric")]
+    FieldNotNumeric(String),
+
+    #[error("{0} is encrypted and no password was given")]
+    PasswordRequired(String),
+
+    #[error("Wrong password for {0}")]
+    InvalidPassword(String),
+
+    #[cfg(feature = "geo-types")]
+    #[error("Geometry conversion error: {0}")]
+    GeometryConversion(&'static str),
+
+    #[error("{0} shapes aren't supported here")]
+    UnsupportedShapeType(shapefile::ShapeType),
+
+    #[cfg(feature = "proj")]
+    #[error("Couldn't tell the CRS from the .prj")]
+    UnknownCrs,
+
+    #[cfg(feature = "proj")]
+    #[error("Couldn't set up the reprojection")]
+    ProjCreate(#[from] proj::ProjCreateError),
+
+    #[cfg(feature = "proj")]
+    #[error("Reprojection failed")]
+    Proj(#[from] proj::ProjError),
+
+    #[cfg(feature = "serde")]
+    #[error("Couldn't deserialize record {index}: {source}")]
+    Deserialize {
+        index: usize,
+        source: serde_json::Error,
+    },
+
+    #[cfg(any(feature = "arrow", feature = "geopackage", feature = "polars"))]
+    #[error("Field {0} clashes with a column of the same name")]
+    ReservedColumnName(String),
+
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error")]
+    Arrow(#[from] arrow::error::ArrowError),
+
+    #[cfg(feature = "polars")]
+    #[error("Polars error")]
+    Polars(#[from] polars::error::PolarsError),
+
+    #[cfg(feature = "geopackage")]
+    #[error("SQLite error")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[cfg(feature = "tokio")]
+    #[error("Blocking task failed")]
+    Join(#[from] tokio::task::JoinError),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-pub struct ZippedShapefile<R> {
-    archive: ZipArchive<R>,
+/// Members are read (and decompressed) into memory before being handed to
+/// the `shapefile` and `dbase` readers. The contents are shared with the
+/// cache, so another reader over a member that's been read is cheap.
+type Buffer = Cursor<Arc<[u8]>>;
+
+#[cfg(feature = "streaming")]
+/// How big a member `shape_reader_streaming()` will hold in memory before
+/// moving it to a temporary file.
+pub const DEFAULT_SPILL_THRESHOLD: usize = 64 * 1024 * 1024;
+
+/// Which coordinates the shapes of a layer carry besides `x` and `y`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dimension {
+    /// Plain shapes, and layers of nothing but null shapes.
+    Xy,
+    Xyz,
+    Xym,
+    Xyzm,
+}
+
+/// One or more shapefiles read out of some [`Container`].
+pub struct Shapefile<C> {
+    container: C,
+    /// The names of everything in the container, components or not.
+    members: Vec<String>,
+    /// The members that aren't a component we know how to read.
+    sidecars: Vec<String>,
+    /// The default layer's `.prj`, or the only one in the container when
+    /// there are several layers.
     projection: Option<String>,
+    /// The `.prj` of each layer that has its own, when there are several
+    /// layers.
+    layer_projections: HashMap<String, String>,
+    /// The `.prj`s read so far, by member name: they're only read once
+    /// asked for.
+    projections: HashMap<String, Projection>,
+    prj_lossy: bool,
+    /// The default layer's encoding, or the one applying to every layer
+    /// when there are several.
+    encoding: Option<String>,
+    /// The encoding of each layer that has a `.cpg` of its own, when there
+    /// are several layers.
+    layer_encodings: HashMap<String, String>,
+    /// Whether there's a `.cpg`, whether or not `encoding` came from it.
+    has_cpg: bool,
+    /// What `uncompressed_size()` gives, found out when opening.
+    uncompressed_size: u64,
+    /// The layer read by `shape_reader()` and friends; `None` when the
+    /// container holds several and callers have to pick one with `layer()`.
+    default: Option<LayerMembers>,
+    layers: BTreeMap<String, LayerMembers>,
+    #[cfg(feature = "streaming")]
+    spill_threshold: usize,
+    tolerate_stale_shx: bool,
+    /// Whether a `.shx` had to be rebuilt because it didn't fit its `.shp`.
+    found_stale_shx: bool,
+    max_member_size: Option<u64>,
+    max_compression_ratio: Option<f64>,
+    /// The members read so far (within the cache budget, if there is one),
+    /// so that each gets decompressed only once.
+    cache: Cache,
+    /// The `.shx`s rebuilt for `.shp`s that came without one, by `.shp`.
+    indexes: HashMap<String, Arc<[u8]>>,
+    /// The reader behind the iterator handed out by `iter()`, kept here so
+    /// that the iterator can borrow it.
+    iterated: Option<Reader<Buffer, Buffer>>,
+    /// The same for `iter_shapes_only()`.
+    iterated_shapes: Option<ShapeReader<Buffer>>,
+    /// And for `iter_attributes()`.
+    iterated_records: Option<dbase::Reader<Buffer>>,
+}
+
+/// Names the default layer's components and counts the members, leaving
+/// out the container and everything read from it.
+impl<C> fmt::Debug for Shapefile<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let layer = self.default.as_ref();
+        f.debug_struct("Shapefile")
+            .field("shp", &layer.map(|layer| &layer.shp))
+            .field("shx", &layer.and_then(|layer| layer.shx.as_ref()))
+            .field("dbf", &layer.and_then(|layer| layer.dbf.as_ref()))
+            .field("has_prj", &self.projection.is_some())
+            .field("layers", &self.layers.len())
+            .field("members", &self.members.len())
+            .finish()
+    }
+}
+
+/// Which of the optional components the default layer has, as found when
+/// the container was opened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayerComponents {
+    /// The layer's name, as `layers()` has it.
+    pub stem: String,
+    pub has_shx: bool,
+    pub has_dbf: bool,
+    pub has_prj: bool,
+    pub has_cpg: bool,
+}
+
+/// A `.prj`, both as it is and decoded.
+struct Projection {
+    text: String,
+    bytes: Vec<u8>,
+}
+
+/// The members making up a single shapefile within the container.
+#[derive(Clone, Debug)]
+struct LayerMembers {
     shp: String,
     shx: Option<String>,
     dbf: Option<String>,
+    /// The `.dbt`/`.fpt` holding the DBF's memo fields.
+    memo: Option<String>,
+    /// The `.shp.xml` holding FGDC or ISO 19115 metadata.
+    metadata: Option<String>,
+}
+
+/// Every shapefile component in the container, by extension.
+#[derive(Default)]
+struct Members {
+    shp: Vec<String>,
+    shx: Vec<String>,
+    dbf: Vec<String>,
+    memo: Vec<String>,
+    prj: Vec<String>,
+    cpg: Vec<String>,
+}
+
+/// Memo files come in two flavours, but a DBF only ever uses one.
+const MEMO: &str = ".dbt/.fpt";
+
+/// `member` without its extension. Directories are kept, so that archives
+/// with `a/parcels.shp` and `b/parcels.shp` hold two distinct layers.
+fn stem(member: &str) -> &str {
+    let name = member.rfind(['/', '\\']).map_or(0, |slash| slash + 1);
+    member[name..]
+        .rfind('.')
+        .map_or(member, |dot| &member[..name + dot])
+}
+
+/// `stem` with any backslashes (which old Windows tools store as the
+/// directory separator) turned into slashes.
+fn layer_name(stem: &str) -> String {
+    stem.replace('\\', "/")
+}
+
+/// Whether two stems name the same layer: case doesn't matter, like for
+/// extensions, and neither does the directory separator.
+fn same_stem(a: &str, b: &str) -> bool {
+    layer_name(a).to_lowercase() == layer_name(b).to_lowercase()
+}
+
+/// The only member in `members`, if there is one.
+fn single(mut members: Vec<String>, extension: &'static str) -> Result<Option<String>> {
+    if members.len() > 1 {
+        return Err(Error::MultipleFilesFound(extension));
+    }
+    Ok(members.pop())
+}
+
+/// The only member in `members` whose stem is `stem` (ignoring case, like
+/// extensions), if there is one.
+fn single_with_stem(
+    members: &[String],
+    stem: &str,
+    extension: &'static str,
+) -> Result<Option<String>> {
+    let matching = members
+        .iter()
+        .filter(|member| same_stem(self::stem(member), stem))
+        .cloned()
+        .collect();
+    single(matching, extension)
+}
+
+/// The sidecar named like `shp` with `.xml` tacked on, as ArcGIS names its
+/// metadata; extensions are matched ignoring case, like components.
+fn metadata_member(sidecars: &[String], shp: &str) -> Option<String> {
+    let name = format!("{}.xml", shp).to_lowercase();
+    sidecars
+        .iter()
+        .find(|sidecar| sidecar.to_lowercase() == name)
+        .cloned()
+}
+
+/// Sidecars with a stray non-UTF-8 byte are still mostly usable, and not
+/// worth refusing the whole shapefile over. Windows tools sometimes start
+/// them with a byte order mark, which is dropped.
+fn lossy_text(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Like `lossy_text()`, but for a `.prj` that has to be UTF-8.
+fn strict_text(bytes: &[u8]) -> Result<String> {
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+    String::from_utf8(bytes.to_vec()).map_err(|_| Error::NonUtf8Projection)
+}
+
+/// Says which member `err` came from, when it's from reading one.
+fn member_error(name: &str, err: Error) -> Error {
+    match err {
+        Error::IOError(_) | Error::Zip(_) => Error::MemberRead {
+            name: name.to_owned(),
+            source: Box::new(err),
+        },
+        err => err,
+    }
+}
+
+/// Fails with `MemberSizeTooLarge` if the named member claims to be bigger
+/// than `max_size`, or with `SuspiciousCompressionRatio` if it claims to
+/// decompress to more than `max_ratio` times its compressed size, without
+/// reading any of it.
+fn check_member<C: Container>(
+    container: &mut C,
+    name: &str,
+    max_size: Option<u64>,
+    max_ratio: Option<f64>,
+) -> Result<()> {
+    if max_size.is_none() && max_ratio.is_none() {
+        return Ok(());
+    }
+    let size = container
+        .member_size(name)
+        .map_err(|err| member_error(name, err))?;
+    if let Some(max) = max_size {
+        if size > max {
+            return Err(Error::MemberSizeTooLarge(size));
+        }
+    }
+    if let Some(max) = max_ratio {
+        let compressed = container
+            .member_compressed_size(name)
+            .map_err(|err| member_error(name, err))?;
+        if let Some(compressed) = compressed {
+            let ratio = size as f64 / compressed.max(1) as f64;
+            if ratio > max {
+                return Err(Error::SuspiciousCompressionRatio {
+                    name: name.to_owned(),
+                    ratio,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads the named member, once `check_member()` is happy with it.
+fn read_capped<C: Container>(container: &mut C, name: &str, options: &Options) -> Result<Vec<u8>> {
+    check_member(
+        container,
+        name,
+        options.max_member_size,
+        options.max_compression_ratio,
+    )?;
+    container
+        .read_member(name)
+        .map_err(|err| member_error(name, err))
+}
+
+/// The `[min_x, min_y, max_x, max_y]` extent of `shape`, or `None` for null
+/// shapes, which have no extent.
+fn shape_bbox(shape: &Shape) -> Option<[f64; 4]> {
+    fn point<P: HasXY>(point: &P) -> [f64; 4] {
+        [point.x(), point.y(), point.x(), point.y()]
+    }
+    fn extent<P: HasXY>(bbox: &GenericBBox<P>) -> [f64; 4] {
+        [bbox.min.x(), bbox.min.y(), bbox.max.x(), bbox.max.y()]
+    }
+
+    Some(match shape {
+        Shape::NullShape => return None,
+        Shape::Point(p) => point(p),
+        Shape::PointM(p) => point(p),
+        Shape::PointZ(p) => point(p),
+        Shape::Polyline(line) => extent(line.bbox()),
+        Shape::PolylineM(line) => extent(line.bbox()),
+        Shape::PolylineZ(line) => extent(line.bbox()),
+        Shape::Polygon(polygon) => extent(polygon.bbox()),
+        Shape::PolygonM(polygon) => extent(polygon.bbox()),
+        Shape::PolygonZ(polygon) => extent(polygon.bbox()),
+        Shape::Multipoint(points) => extent(points.bbox()),
+        Shape::MultipointM(points) => extent(points.bbox()),
+        Shape::MultipointZ(points) => extent(points.bbox()),
+        Shape::Multipatch(patch) => extent(patch.bbox()),
+    })
+}
+
+/// `shape` as a `geo_types::Geometry`. Multipatches made of triangle
+/// strips or fans have no equivalent, and fail with `UnsupportedShapeType`
+/// so that they can be told apart from other conversion errors.
+#[cfg(feature = "geo-types")]
+fn shape_geometry(shape: Shape) -> Result<geo_types::Geometry<f64>> {
+    let shape_type = shape.shapetype();
+    geo_types::Geometry::<f64>::try_from(shape).map_err(|err| match shape_type {
+        shapefile::ShapeType::Multipatch => Error::UnsupportedShapeType(shape_type),
+        _ => Error::GeometryConversion(err),
+    })
+}
+
+/// How many points `shape` has, across all its parts, rings or patches.
+fn vertex_count(shape: &Shape) -> usize {
+    match shape {
+        Shape::NullShape => 0,
+        Shape::Point(_) | Shape::PointM(_) | Shape::PointZ(_) => 1,
+        Shape::Polyline(line) => line.total_point_count(),
+        Shape::PolylineM(line) => line.total_point_count(),
+        Shape::PolylineZ(line) => line.total_point_count(),
+        Shape::Polygon(polygon) => polygon.total_point_count(),
+        Shape::PolygonM(polygon) => polygon.total_point_count(),
+        Shape::PolygonZ(polygon) => polygon.total_point_count(),
+        Shape::Multipoint(points) => points.points().len(),
+        Shape::MultipointM(points) => points.points().len(),
+        Shape::MultipointZ(points) => points.points().len(),
+        Shape::Multipatch(patch) => patch.total_point_count(),
+    }
+}
+
+/// How many parts `shape` has: its line strings, rings or patches. Each
+/// point of a multipoint counts as a part of its own, the way it becomes
+/// one point of a `MultiPoint`.
+fn part_count(shape: &Shape) -> usize {
+    match shape {
+        Shape::NullShape => 0,
+        Shape::Point(_) | Shape::PointM(_) | Shape::PointZ(_) => 1,
+        Shape::Polyline(line) => line.parts().len(),
+        Shape::PolylineM(line) => line.parts().len(),
+        Shape::PolylineZ(line) => line.parts().len(),
+        Shape::Polygon(polygon) => polygon.rings().len(),
+        Shape::PolygonM(polygon) => polygon.rings().len(),
+        Shape::PolygonZ(polygon) => polygon.rings().len(),
+        Shape::Multipoint(points) => points.points().len(),
+        Shape::MultipointM(points) => points.points().len(),
+        Shape::MultipointZ(points) => points.points().len(),
+        Shape::Multipatch(patch) => patch.patches().len(),
+    }
+}
+
+/// The name of `shape`'s variant. `Shape::shapetype` would do, but it
+/// mistakes `MultipointM`s and `MultipointZ`s for plain `Multipoint`s.
+fn variant_name(shape: &Shape) -> &'static str {
+    match shape {
+        Shape::NullShape => "NullShape",
+        Shape::Point(_) => "Point",
+        Shape::PointM(_) => "PointM",
+        Shape::PointZ(_) => "PointZ",
+        Shape::Polyline(_) => "Polyline",
+        Shape::PolylineM(_) => "PolylineM",
+        Shape::PolylineZ(_) => "PolylineZ",
+        Shape::Polygon(_) => "Polygon",
+        Shape::PolygonM(_) => "PolygonM",
+        Shape::PolygonZ(_) => "PolygonZ",
+        Shape::Multipoint(_) => "Multipoint",
+        Shape::MultipointM(_) => "MultipointM",
+        Shape::MultipointZ(_) => "MultipointZ",
+        Shape::Multipatch(_) => "Multipatch",
+    }
 }
 
+/// The type of `shape`, going by its variant, for the same reason as
+/// `variant_name`.
+fn shape_type(shape: &Shape) -> shapefile::ShapeType {
+    use shapefile::ShapeType;
+
+    match shape {
+        Shape::NullShape => ShapeType::NullShape,
+        Shape::Point(_) => ShapeType::Point,
+        Shape::PointM(_) => ShapeType::PointM,
+        Shape::PointZ(_) => ShapeType::PointZ,
+        Shape::Polyline(_) => ShapeType::Polyline,
+        Shape::PolylineM(_) => ShapeType::PolylineM,
+        Shape::PolylineZ(_) => ShapeType::PolylineZ,
+        Shape::Polygon(_) => ShapeType::Polygon,
+        Shape::PolygonM(_) => ShapeType::PolygonM,
+        Shape::PolygonZ(_) => ShapeType::PolygonZ,
+        Shape::Multipoint(_) => ShapeType::Multipoint,
+        Shape::MultipointM(_) => ShapeType::MultipointM,
+        Shape::MultipointZ(_) => ShapeType::MultipointZ,
+        Shape::Multipatch(_) => ShapeType::Multipatch,
+    }
+}
+
+/// Stretches the `[min_x, min_y, max_x, max_y]` `extent` to cover every
+/// coordinate of `shape`.
+fn extend_extent(extent: &mut Option<[f64; 4]>, shape: &Shape) {
+    fn points<P: HasXY>(extent: &mut Option<[f64; 4]>, points: &[P]) {
+        for point in points {
+            let (x, y) = (point.x(), point.y());
+            *extent = Some(match *extent {
+                Some([min_x, min_y, max_x, max_y]) => {
+                    [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+                }
+                None => [x, y, x, y],
+            });
+        }
+    }
+
+    match shape {
+        Shape::NullShape => {}
+        Shape::Point(p) => points(extent, std::slice::from_ref(p)),
+        Shape::PointM(p) => points(extent, std::slice::from_ref(p)),
+        Shape::PointZ(p) => points(extent, std::slice::from_ref(p)),
+        Shape::Polyline(line) => line.parts().iter().for_each(|part| points(extent, part)),
+        Shape::PolylineM(line) => line.parts().iter().for_each(|part| points(extent, part)),
+        Shape::PolylineZ(line) => line.parts().iter().for_each(|part| points(extent, part)),
+        Shape::Polygon(polygon) => polygon
+            .rings()
+            .iter()
+            .for_each(|ring| points(extent, ring.points())),
+        Shape::PolygonM(polygon) => polygon
+            .rings()
+            .iter()
+            .for_each(|ring| points(extent, ring.points())),
+        Shape::PolygonZ(polygon) => polygon
+            .rings()
+            .iter()
+            .for_each(|ring| points(extent, ring.points())),
+        Shape::Multipoint(multi) => points(extent, multi.points()),
+        Shape::MultipointM(multi) => points(extent, multi.points()),
+        Shape::MultipointZ(multi) => points(extent, multi.points()),
+        Shape::Multipatch(patch) => patch
+            .patches()
+            .iter()
+            .for_each(|patch| points(extent, patch.points())),
+    }
+}
+
+/// Whether two `[min_x, min_y, max_x, max_y]` boxes overlap; boxes that
+/// only share an edge or corner count.
+fn bboxes_overlap(a: [f64; 4], b: [f64; 4]) -> bool {
+    a[0] <= b[2] && b[0] <= a[2] && a[1] <= b[3] && b[1] <= a[3]
+}
+
+/// A `.dbf` with no fields and `records` empty records, standing in for a
+/// layer's missing one so that shapes still come paired with a record.
+fn empty_dbf(records: usize) -> Result<dbase::Reader<Buffer>> {
+    Ok(dbase::Reader::new(Cursor::new(
+        empty_dbf_contents(records)?.into(),
+    ))?)
+}
+
+fn empty_dbf_contents(records: usize) -> Result<Vec<u8>> {
+    let mut dbf = Cursor::new(Vec::new());
+    let mut writer = dbase::TableWriterBuilder::new().build_with_dest(&mut dbf);
+    for _ in 0..records {
+        writer.write_record(&Record::default())?;
+    }
+    drop(writer);
+    Ok(dbf.into_inner())
+}
+
+/// Shapefiles read out of a zip archive.
+pub type ZippedShapefile<R> = Shapefile<ZipArchive<R>>;
+
+/// Shapefiles read out of a password-protected zip archive.
+pub type EncryptedZippedShapefile<R> = Shapefile<EncryptedZip<R>>;
+
+/// Shapefiles read from the loose files of a directory.
+pub type DirShapefile = Shapefile<Directory>;
+
+/// Shapefiles read out of a tar archive, gzipped or not.
+#[cfg(feature = "tar")]
+pub type TarShapefile = Shapefile<TarArchive>;
+
 impl ZippedShapefile<std::fs::File> {
     pub fn open<P>(path: P) -> Result<Self>
     where
@@ -57,123 +708,4373 @@ impl ZippedShapefile<std::fs::File> {
     {
         ZippedShapefile::new(File::open(path)?)
     }
+
+    /// Opens a password-protected archive; see
+    /// [`ZippedShapefile::new_with_password`].
+    pub fn open_with_password<P, S>(
+        path: P,
+        password: S,
+    ) -> Result<EncryptedZippedShapefile<std::fs::File>>
+    where
+        P: AsRef<Path>,
+        S: Into<Vec<u8>>,
+    {
+        ZippedShapefile::new_with_password(File::open(path)?, password)
+    }
+
+    /// Opens every archive in `paths` on rayon's thread pool, with a result
+    /// for each, in the same order, so one bad archive doesn't stop the
+    /// rest from opening.
+    #[cfg(feature = "rayon")]
+    pub fn open_all<P, I>(paths: I) -> Vec<Result<Self>>
+    where
+        P: AsRef<Path> + Send,
+        I: IntoIterator<Item = P>,
+    {
+        use rayon::prelude::*;
+
+        paths
+            .into_iter()
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .map(Self::open)
+            .collect()
+    }
 }
 
 impl<R> ZippedShapefile<R>
 where
     R: Read + Seek,
 {
+    /// Opens an archive holding one or more shapefiles; see
+    /// [`Shapefile::with_container`].
     pub fn new(source: R) -> Result<Self> {
-        let mut archive = ZipArchive::new(source)?;
-        let mut shp = None;
-        let mut shx = None;
-        let mut dbf = None;
-        let mut prj = None;
-
-        for member in archive.file_names() {
-            if member.ends_with(".shp") {
-                if shp.is_some() {
-                    return Err(Error::MultipleFilesFound(".shp"));
-                }
-                shp = Some(member.to_owned());
-            } else if member.ends_with(".shx") {
-                if shx.is_some() {
-                    return Err(Error::MultipleFilesFound(".shx"));
+        Self::with_container(container::open_zip(source)?)
+    }
+
+    /// Opens just the shapefile with the given stem, ignoring every other
+    /// member of the archive; see [`Shapefile::with_container_layer`].
+    pub fn open_layer(source: R, stem: &str) -> Result<Self> {
+        Self::with_container_layer(container::open_zip(source)?, stem)
+    }
+
+    /// Opens an archive whose members are encrypted with `password`.
+    /// Encrypted archives opened with [`ZippedShapefile::new`] instead fail
+    /// with [`Error::PasswordRequired`] as soon as an encrypted member is read.
+    pub fn new_with_password<S>(source: R, password: S) -> Result<EncryptedZippedShapefile<R>>
+    where
+        S: Into<Vec<u8>>,
+    {
+        Shapefile::with_container(EncryptedZip::new(container::open_zip(source)?, password))
+    }
+
+    /// The comment stored at the end of the archive, which is often empty.
+    pub fn archive_comment(&self) -> &[u8] {
+        self.container.comment()
+    }
+
+    /// Gives back the reader the archive was opened from.
+    pub fn into_inner(self) -> R {
+        self.container.into_inner()
+    }
+}
+
+impl ZippedShapefile<Cursor<Vec<u8>>> {
+    /// Opens an archive that's already in memory, like an upload.
+    pub fn from_bytes<B>(data: B) -> Result<Self>
+    where
+        B: Into<Vec<u8>>,
+    {
+        Self::new(Cursor::new(data.into()))
+    }
+
+    /// Reads the whole archive from a stream that can't seek, like a pipe or
+    /// a socket, then opens it from memory. See
+    /// `ZippedShapefileBuilder::from_reader()` for capping member sizes.
+    pub fn from_reader<R>(mut reader: R) -> Result<Self>
+    where
+        R: Read,
+    {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Self::from_bytes(data)
+    }
+}
+
+impl ZippedShapefile<Cursor<Vec<u8>>> {
+    /// Opens an archive split into several volumes, as in `data.z01`,
+    /// `data.z02`, …, `data.zip`, given in that order (the `.zip` last).
+    /// The volumes are read into memory and joined back into the archive
+    /// they came from. Zip64 archives fail with
+    /// `Error::SplitArchiveUnsupported`.
+    pub fn open_volumes<I, P>(paths: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let volumes = paths
+            .into_iter()
+            .map(fs::read)
+            .collect::<io::Result<Vec<_>>>()?;
+        Self::from_bytes(split::join(volumes)?)
+    }
+}
+
+impl<'a> ZippedShapefile<Cursor<&'a [u8]>> {
+    /// Opens an archive that's already in memory, without copying it.
+    pub fn from_slice(data: &'a [u8]) -> Result<Self> {
+        Self::new(Cursor::new(data))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl ZippedShapefile<Cursor<Vec<u8>>> {
+    /// Reads the whole archive from `source` into memory, then opens it on
+    /// tokio's blocking thread pool. Only construction is asynchronous: the
+    /// readers and everything else are still synchronous, but since they
+    /// only decompress from memory they never wait on IO.
+    pub async fn new_async<R>(mut source: R) -> Result<Self>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        use tokio::io::AsyncReadExt;
+
+        let mut bytes = Vec::new();
+        source.read_to_end(&mut bytes).await?;
+        tokio::task::spawn_blocking(move || ZippedShapefile::from_bytes(bytes)).await?
+    }
+}
+
+impl DirShapefile {
+    /// Opens a directory holding one or more shapefiles as loose files; see
+    /// [`Shapefile::with_container`].
+    pub fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::with_container(Directory::new(path))
+    }
+}
+
+#[cfg(feature = "tar")]
+impl TarShapefile {
+    /// Reads a tar archive, gzipped or not, from `reader`; see
+    /// [`TarArchive`] and [`Shapefile::with_container`].
+    pub fn from_tar<R>(reader: R) -> Result<Self>
+    where
+        R: Read,
+    {
+        Self::with_container(TarArchive::new(reader)?)
+    }
+
+    pub fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_tar(File::open(path)?)
+    }
+}
+
+impl<C> Shapefile<C>
+where
+    C: Container,
+{
+    /// Finds the shapefiles in `container`, matching the `.shx`/`.dbf`/
+    /// `.prj` to each `.shp` by stem; ones named after no `.shp` are
+    /// ignored. With several `.shp`s only the `layer()` accessors are
+    /// usable.
+    pub fn with_container(container: C) -> Result<Self> {
+        Self::with_members(container, None, Options::default())
+    }
+
+    /// Like `with_container()`, but only looks at the members whose stem is
+    /// `stem` (ignoring case), so strays like `layer_backup.shp` next to
+    /// `layer.shp` don't get in the way. Fails with `LayerNotFound` when
+    /// there's no such `.shp`.
+    pub fn with_container_layer(container: C, stem: &str) -> Result<Self> {
+        Self::with_members(container, Some(stem), Options::default())
+    }
+
+    pub fn into_container(self) -> C {
+        self.container
+    }
+
+    fn with_members(mut container: C, only: Option<&str>, options: Options) -> Result<Self> {
+        let mut members = Members::default();
+
+        let names = container.member_names()?;
+        let mut sidecars = Vec::new();
+        for member in &names {
+            // Windows tools like to shout, so `PARCELS.SHP` is just as good
+            let lowercase = member.to_lowercase();
+            let found = if lowercase.ends_with(".shp") {
+                &mut members.shp
+            } else if lowercase.ends_with(".shx") {
+                &mut members.shx
+            } else if lowercase.ends_with(".dbf") {
+                &mut members.dbf
+            } else if lowercase.ends_with(".dbt") || lowercase.ends_with(".fpt") {
+                &mut members.memo
+            } else if lowercase.ends_with(".prj") {
+                &mut members.prj
+            } else if lowercase.ends_with(".cpg") {
+                &mut members.cpg
+            } else {
+                sidecars.push(member.clone());
+                continue;
+            };
+            if let Some(only) = only {
+                if !same_stem(stem(member), only) {
+                    continue;
                 }
-                shx = Some(member.to_owned())
-            } else if member.ends_with(".dbf") {
-                if dbf.is_some() {
-                    return Err(Error::MultipleFilesFound(".dbf"));
+            }
+            found.push(member.clone());
+        }
+
+        if options.strict {
+            let companions = [
+                &members.shx,
+                &members.dbf,
+                &members.memo,
+                &members.prj,
+                &members.cpg,
+            ];
+            for member in companions.into_iter().flatten() {
+                if !members
+                    .shp
+                    .iter()
+                    .any(|shp| same_stem(stem(shp), stem(member)))
+                {
+                    return Err(Error::UnmatchedComponent(member.clone()));
                 }
-                dbf = Some(member.to_owned())
-            } else if member.ends_with(".prj") {
-                if prj.is_some() {
-                    return Err(Error::MultipleFilesFound(".prj"));
+            }
+        }
+
+        let mut layers = BTreeMap::new();
+        let mut lowercase_stems = HashSet::new();
+        for shp in &members.shp {
+            let stem = stem(shp);
+            if !lowercase_stems.insert(layer_name(stem).to_lowercase()) {
+                return Err(Error::MultipleFilesFound(".shp"));
+            }
+            let layer = LayerMembers {
+                shp: shp.clone(),
+                shx: single_with_stem(&members.shx, stem, ".shx")?,
+                dbf: single_with_stem(&members.dbf, stem, ".dbf")?,
+                memo: single_with_stem(&members.memo, stem, MEMO)?,
+                metadata: metadata_member(&sidecars, shp),
+            };
+            layers.insert(layer_name(stem), layer);
+        }
+
+        let default = match &members.shp[..] {
+            [] => {
+                return Err(match only {
+                    Some(only) => Error::LayerNotFound(only.to_owned()),
+                    None => Error::NoShpFound,
+                })
+            }
+            [shp] => layers.get(&layer_name(stem(shp))).cloned(),
+            _ => None,
+        };
+
+        // with several layers, each can have a `.prj` of its own
+        let mut layer_projections = HashMap::new();
+        if default.is_none() {
+            for (name, layer) in &layers {
+                if let Some(prj) = single_with_stem(&members.prj, stem(&layer.shp), ".prj")? {
+                    layer_projections.insert(name.clone(), prj);
                 }
-                prj = Some(member.to_owned());
             }
         }
+        let projection = match (&default, &members.prj[..]) {
+            (Some(layer), _) => single_with_stem(&members.prj, stem(&layer.shp), ".prj")?,
+            (None, [_, _, ..]) => None,
+            (None, _) => single(members.prj, ".prj")?,
+        };
+        for (name, layer) in &layers {
+            let required = options.required;
+            if required.contains(Components::SHX) && layer.shx.is_none() {
+                return Err(Error::MissingShx(name.clone()));
+            }
+            if required.contains(Components::DBF) && layer.dbf.is_none() {
+                return Err(Error::MissingDbf(name.clone()));
+            }
+            // as `layer_prj()` finds them
+            let prj = if layer_projections.is_empty() {
+                projection.as_ref()
+            } else {
+                layer_projections.get(name)
+            };
+            if required.contains(Components::PRJ) && prj.is_none() {
+                return Err(Error::MissingProjection(name.clone()));
+            }
+        }
+
+        // sized now, while the container is at hand: its metadata is all
+        // that takes
+        let sized: Vec<&LayerMembers> = match &default {
+            Some(layer) => vec![layer],
+            None => layers.values().collect(),
+        };
+        let mut components: BTreeSet<&String> = projection
+            .iter()
+            .chain(layer_projections.values())
+            .collect();
+        for layer in sized {
+            let parts = [&layer.shx, &layer.dbf, &layer.memo];
+            components.insert(&layer.shp);
+            components.extend(parts.into_iter().flatten());
+        }
+        let mut uncompressed_size = 0;
+        for name in components {
+            uncompressed_size += container
+                .member_size(name)
+                .map_err(|err| member_error(name, err))?;
+        }
 
-        let projection = if let Some(prj) = prj {
-            let mut wkt = String::new();
-            let mut wkt_buf = archive.by_name(&prj)?;
-            wkt_buf.read_to_string(&mut wkt)?;
-            Some(wkt)
+        // and a `.cpg`, the same way; an encoding given by the caller beats
+        // them all
+        let mut layer_encodings = HashMap::new();
+        if default.is_none() && options.encoding.is_none() {
+            for (name, layer) in &layers {
+                if let Some(cpg) = single_with_stem(&members.cpg, stem(&layer.shp), ".cpg")? {
+                    let encoding = lossy_text(&read_capped(&mut container, &cpg, &options)?);
+                    layer_encodings.insert(name.clone(), encoding.trim().to_owned());
+                }
+            }
+        }
+        let cpg = match (&default, &members.cpg[..]) {
+            (Some(layer), _) => single_with_stem(&members.cpg, stem(&layer.shp), ".cpg")?,
+            (None, [_, _, ..]) => None,
+            (None, _) => single(members.cpg, ".cpg")?,
+        };
+        let has_cpg = cpg.is_some();
+        let encoding = if options.encoding.is_some() {
+            options.encoding
+        } else if let Some(cpg) = cpg {
+            Some(
+                lossy_text(&read_capped(&mut container, &cpg, &options)?)
+                    .trim()
+                    .to_owned(),
+            )
+        } else if let Some(dbf) = default.as_ref().and_then(|layer| layer.dbf.as_ref()) {
+            let head = container
+                .read_member_head(dbf, 32)
+                .map_err(|err| member_error(dbf, err))?;
+            cpg::ldid(&head)
+                .and_then(cpg::ldid_codepage)
+                .map(|codepage| format!("CP{}", codepage))
         } else {
             None
         };
 
-        match shp {
-            Some(shp) => Ok(Self {
-                archive,
-                projection,
-                shp,
-                shx,
-                dbf,
-            }),
-            None => Err(Error::NoShpFound),
+        Ok(Self {
+            container,
+            members: names,
+            sidecars,
+            projection,
+            layer_projections,
+            projections: HashMap::new(),
+            prj_lossy: options.prj_lossy,
+            encoding,
+            layer_encodings,
+            has_cpg,
+            uncompressed_size,
+            default,
+            layers,
+            #[cfg(feature = "streaming")]
+            spill_threshold: DEFAULT_SPILL_THRESHOLD,
+            tolerate_stale_shx: false,
+            found_stale_shx: false,
+            max_member_size: options.max_member_size,
+            max_compression_ratio: options.max_compression_ratio,
+            cache: Cache::default(),
+            indexes: HashMap::new(),
+            iterated: None,
+            iterated_shapes: None,
+            iterated_records: None,
+        })
+    }
+
+    fn read_member(&mut self, name: &str) -> Result<Buffer> {
+        self.check_member(name)?;
+        if let Some(contents) = self.cache.get(name) {
+            return Ok(Cursor::new(contents));
         }
+        let contents: Arc<[u8]> = self
+            .container
+            .read_member(name)
+            .map_err(|err| member_error(name, err))?
+            .into();
+        self.cache.insert(name, contents.clone());
+        Ok(Cursor::new(contents))
     }
 
-    fn read_member(&mut self, name: &str) -> Result<Cursor<Vec<u8>>> {
-        let mut zf = self.archive.by_name(name)?;
-        let size: usize = zf
-            .size()
-            .try_into()
-            .map_err(|_| Error::MemberSizeTooLarge(zf.size()))?;
-        let mut buf = Vec::with_capacity(size);
-        assert_eq!(size, zf.read_to_end(&mut buf)?);
-        Ok(Cursor::new(buf))
+    /// The name of every member of the container, including the ones not
+    /// belonging to any shapefile.
+    pub fn members(&self) -> Vec<String> {
+        self.members.clone()
     }
 
-    pub fn projection(&self) -> Option<&str> {
-        self.projection.as_deref()
+    /// The members that aren't shapefile components this crate reads, like
+    /// `.sbn` indexes, `.xml` metadata or a `README.txt`.
+    pub fn sidecar_members(&self) -> Vec<String> {
+        self.sidecars.clone()
     }
 
-    pub fn shape_reader(&mut self) -> Result<ShapeReader<Cursor<Vec<u8>>>> {
-        let shp = self.shp.clone();
-        let shx = self.shx.clone();
-        let shp_reader = self.read_member(&shp)?;
-        Ok(if let Some(shx) = &shx {
-            ShapeReader::with_shx(shp_reader, self.read_member(shx)?)
-        } else {
-            ShapeReader::new(shp_reader)
-        }?)
+    /// The contents of the named member, exactly as stored (once
+    /// decompressed), for things like `.sbn` indexes or `.xml` metadata that
+    /// this crate doesn't read itself. Any member will do, not just
+    /// sidecars; the name has to match exactly. The size caps and cache
+    /// apply as for components.
+    pub fn read_sidecar(&mut self, name: &str) -> Result<Vec<u8>> {
+        if !self.members.iter().any(|member| member == name) {
+            return Err(Error::MemberNotFound(name.to_owned()));
+        }
+        Ok(self.read_member(name)?.into_inner().to_vec())
     }
 
-    pub fn dbf_reader(&mut self) -> Result<Option<dbase::Reader<Cursor<Vec<u8>>>>> {
-        match self.dbf.clone() {
-            Some(dbf) => Ok(Some(dbase::Reader::new(self.read_member(&dbf)?)?)),
+    /// Writes the default layer's `.shp`, `.shx`, `.dbf` (and memo file) and
+    /// `.prj` into `dir`, for tools that want a path to an unpacked
+    /// shapefile, returning the path of the `.shp`. The files keep the
+    /// layer's stem, without any directories it had in the container, and
+    /// get lowercase extensions. A missing `.shx` is rebuilt, and a `.cpg`
+    /// is written when `encoding()` knows the codepage. Existing files are
+    /// overwritten.
+    pub fn extract_to<P>(&mut self, dir: P) -> Result<PathBuf>
+    where
+        P: AsRef<Path>,
+    {
+        let layer = self.default_layer()?;
+        let stem = layer_name(stem(&layer.shp));
+        let stem = stem.rsplit('/').next().unwrap_or_default().to_owned();
+        self.extract_with_stem(dir, &stem)
+    }
+
+    /// Like `extract_to()`, but with the files named `stem` plus their
+    /// extension instead.
+    pub fn extract_with_stem<P>(&mut self, dir: P, stem: &str) -> Result<PathBuf>
+    where
+        P: AsRef<Path>,
+    {
+        let layer = self.default_layer()?;
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let path = |extension: &str| dir.join(format!("{}.{}", stem, extension));
+
+        let shp = path("shp");
+        self.extract_member(&layer.shp, &shp)?;
+        match &layer.shx {
+            Some(shx) => self.extract_member(shx, &path("shx"))?,
+            None => fs::write(path("shx"), self.layer_shx(&layer)?.into_inner())?,
+        }
+        if let Some(dbf) = &layer.dbf {
+            self.extract_member(dbf, &path("dbf"))?;
+        }
+        if let Some(memo) = &layer.memo {
+            let extension = memo[memo.len() - 3..].to_lowercase();
+            self.extract_member(memo, &path(&extension))?;
+        }
+        if let Some(prj) = self.projection_bytes()? {
+            fs::write(path("prj"), prj)?;
+        }
+        if let Some(encoding) = self.encoding() {
+            fs::write(path("cpg"), encoding)?;
+        }
+        Ok(shp)
+    }
+
+    /// Copies the named member out to a file at `path`, without holding it
+    /// all in memory.
+    fn extract_member(&mut self, name: &str, path: &Path) -> Result<()> {
+        self.check_member(name)?;
+        let mut file = File::create(path)?;
+        self.container
+            .copy_member(name, &mut file)
+            .map_err(|err| member_error(name, err))?;
+        Ok(())
+    }
+
+    /// The contents of the default layer's `foo.shp.xml` metadata (FGDC or
+    /// ISO 19115, say), decoded like the `.prj` but not parsed. A plain
+    /// `foo.xml` doesn't count.
+    pub fn metadata_xml(&mut self) -> Result<Option<String>> {
+        match self.default_layer()?.metadata {
+            Some(metadata) => Ok(Some(lossy_text(&self.read_member(&metadata)?.into_inner()))),
             None => Ok(None),
         }
     }
 
-    pub fn reader(&mut self) -> Result<Reader<Cursor<Vec<u8>>>> {
-        let dbf = self
-            .dbf_reader()
-            .transpose()
-            .unwrap_or(Err(Error::NoDbfFound))?;
-        let shp = self.shape_reader()?;
-        Ok(Reader::new(shp, dbf))
+    /// Drops the members kept around by the readers, which will read them
+    /// again from the container when next needed.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+        self.indexes = HashMap::new();
     }
 
-    pub fn types(&mut self) -> Result<Option<Vec<(String, String)>>> {
-        // `field_type` is private alas but we can still turn it into a string
-        Ok(self.dbf_reader()?.map(|reader| {
-            reader
-                .fields()
-                .iter()
-                .map(|field| (field.name().to_owned(), field.field_type().to_string()))
-                .collect()
-        }))
+    /// How many bytes of decompressed members the cache holds at most;
+    /// `None`, the default, means no limit.
+    pub fn cache_capacity_bytes(&self) -> Option<usize> {
+        self.cache.capacity()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        let result = 2 + 2;
-        assert_eq!(result, 4);
+    /// Limits the cache to `bytes` of decompressed members, dropping the
+    /// least recently used ones to make room. Members bigger than that on
+    /// their own are never cached, so they get read again each time.
+    pub fn set_cache_capacity_bytes(&mut self, bytes: usize) {
+        self.cache.set_capacity(bytes);
+    }
+
+    /// How many times a member was found in the cache, how many times it had
+    /// to be read from the container, and how many bytes the cache holds.
+    pub fn cache_stats(&self) -> (u64, u64, u64) {
+        self.cache.stats()
+    }
+
+    /// Checks the member against the caps before it gets read.
+    fn check_member(&mut self, name: &str) -> Result<()> {
+        check_member(
+            &mut self.container,
+            name,
+            self.max_member_size,
+            self.max_compression_ratio,
+        )
+    }
+
+    /// Like `read_member()`, but members bigger than the spill threshold end
+    /// up in a temporary file instead of memory.
+    #[cfg(feature = "streaming")]
+    fn spool_member(&mut self, name: &str) -> Result<SpooledTempFile> {
+        self.check_member(name)?;
+        let mut spooled = SpooledTempFile::new(self.spill_threshold);
+        self.container
+            .copy_member(name, &mut spooled)
+            .map_err(|err| member_error(name, err))?;
+        spooled.rewind()?;
+        Ok(spooled)
+    }
+
+    /// The size in bytes past which `shape_reader_streaming()` spills members
+    /// to disk; [`DEFAULT_SPILL_THRESHOLD`] unless changed.
+    #[cfg(feature = "streaming")]
+    pub fn spill_threshold(&self) -> usize {
+        self.spill_threshold
+    }
+
+    #[cfg(feature = "streaming")]
+    pub fn set_spill_threshold(&mut self, bytes: usize) {
+        self.spill_threshold = bytes;
+    }
+
+    /// Whether a `.shx` pointing past the end of its `.shp` gets rebuilt
+    /// from the `.shp` instead of being used as it is; off by default.
+    pub fn tolerate_stale_shx(&self) -> bool {
+        self.tolerate_stale_shx
+    }
+
+    /// Rebuilds `.shx`s that don't fit their `.shp` (having more records
+    /// than it does, say) rather than letting the readers fail on them,
+    /// which `found_stale_shx()` then reports. Checking means reading the
+    /// `.shx`, so `len()` no longer gets away with just its size.
+    /// `shape_reader_streaming()` always uses the `.shx` as it is.
+    pub fn set_tolerate_stale_shx(&mut self, tolerate: bool) {
+        self.tolerate_stale_shx = tolerate;
+    }
+
+    /// Whether some `.shx` had to be rebuilt for not fitting its `.shp`.
+    pub fn found_stale_shx(&self) -> bool {
+        self.found_stale_shx
+    }
+
+    /// The largest (uncompressed) member the readers will read, if there's
+    /// a cap; by default there isn't.
+    pub fn max_member_size(&self) -> Option<u64> {
+        self.max_member_size
+    }
+
+    /// Makes the readers fail with `MemberSizeTooLarge` for members whose
+    /// declared size is over `bytes`, before anything gets allocated for
+    /// them. Note that the `.cpg` has already been read by the time this can
+    /// be called; `ZippedShapefileBuilder::max_member_size()` covers it too.
+    pub fn set_max_member_size(&mut self, bytes: u64) {
+        self.max_member_size = Some(bytes);
+    }
+
+    pub fn max_compression_ratio(&self) -> Option<f64> {
+        self.max_compression_ratio
+    }
+
+    /// Makes the readers fail with `SuspiciousCompressionRatio` for members
+    /// claiming to decompress to more than `ratio` times their compressed
+    /// size (a sign of a zip bomb), before decompressing any of them. Only
+    /// containers that compress, like zip archives, are checked. As with
+    /// `set_max_member_size()`, the `.cpg` has already been read.
+    pub fn set_max_compression_ratio(&mut self, ratio: f64) {
+        self.max_compression_ratio = Some(ratio);
+    }
+
+    /// The WKT from the `.prj`. Bytes that aren't UTF-8 are replaced with
+    /// `U+FFFD` (unless the builder's `prj_lossy(false)` says otherwise);
+    /// see `projection_bytes()` for the file as it is. The `.prj` is only
+    /// read the first time it's asked for, so the size caps apply to it.
+    ///
+    /// With several layers this is only there when the container holds a
+    /// single `.prj`; see `layer_projection()` for when there's one each.
+    ///
+    /// # Errors
+    ///
+    /// Since the `.prj` is read here rather than when opening, this (like
+    /// `projection_bytes()`, `projection_epsg()`, `crs()` and the
+    /// `layer_projection*()` methods) fails when it can't be: it's too big
+    /// for the size caps, the container can't read it, or it isn't UTF-8
+    /// with `prj_lossy(false)`. `Ok(None)` only ever means there's no
+    /// `.prj`.
+    pub fn projection(&mut self) -> Result<Option<&str>> {
+        let prj = self.projection.clone();
+        Ok(self.read_prj(prj)?.map(|prj| prj.text.as_str()))
+    }
+
+    /// The raw contents of the `.prj`.
+    pub fn projection_bytes(&mut self) -> Result<Option<&[u8]>> {
+        let prj = self.projection.clone();
+        Ok(self.read_prj(prj)?.map(|prj| prj.bytes.as_slice()))
+    }
+
+    /// The WKT from the `.prj` sharing the stem of the named layer's
+    /// `.shp`. When no layer has a `.prj` of its own, a lone `.prj` applies
+    /// to all of them. Fails with `LayerNotFound` when there's no such layer.
+    pub fn layer_projection(&mut self, stem: &str) -> Result<Option<&str>> {
+        let prj = self.layer_prj(stem)?;
+        Ok(self.read_prj(prj)?.map(|prj| prj.text.as_str()))
+    }
+
+    /// The raw contents of the `.prj` behind `layer_projection()`.
+    pub fn layer_projection_bytes(&mut self, stem: &str) -> Result<Option<&[u8]>> {
+        let prj = self.layer_prj(stem)?;
+        Ok(self.read_prj(prj)?.map(|prj| prj.bytes.as_slice()))
+    }
+
+    /// The name of the `.prj` for the named layer, if it has one.
+    fn layer_prj(&self, stem: &str) -> Result<Option<String>> {
+        let name = layer_name(stem);
+        if !self.layers.contains_key(&name) {
+            return Err(Error::LayerNotFound(stem.to_owned()));
+        }
+        if self.layer_projections.is_empty() {
+            return Ok(self.projection.clone());
+        }
+        Ok(self.layer_projections.get(&name).cloned())
+    }
+
+    /// Reads and decodes the named `.prj`, unless that's been done already.
+    fn read_prj(&mut self, prj: Option<String>) -> Result<Option<&Projection>> {
+        let prj = match prj {
+            Some(prj) => prj,
+            None => return Ok(None),
+        };
+        if !self.projections.contains_key(&prj) {
+            let bytes = self.read_member(&prj)?.into_inner().to_vec();
+            let text = if self.prj_lossy {
+                lossy_text(&bytes)
+            } else {
+                strict_text(&bytes)?
+            };
+            self.projections
+                .insert(prj.clone(), Projection { text, bytes });
+        }
+        Ok(self.projections.get(&prj))
+    }
+
+    /// The codepage used to decode DBF text fields: the one declared by the
+    /// `.cpg` member, or else one like `CP1252` named by the DBF's language
+    /// driver ID. When neither names a codepage we know, text is decoded as
+    /// UTF-8. With several layers, each can have its own: see
+    /// `layer_encoding()`.
+    pub fn encoding(&self) -> Option<&str> {
+        self.encoding.as_deref()
+    }
+
+    /// The codepage used to decode the named layer's DBF text fields: the
+    /// one declared by the `.cpg` sharing its stem, a lone `.cpg` when no
+    /// layer has one of its own, or the one given to the builder. Fails
+    /// with `LayerNotFound` when there's no such layer.
+    pub fn layer_encoding(&self, stem: &str) -> Result<Option<&str>> {
+        let layer = self
+            .layers
+            .get(&layer_name(stem))
+            .ok_or_else(|| Error::LayerNotFound(stem.to_owned()))?;
+        Ok(self.dbf_encoding(layer))
+    }
+
+    fn dbf_encoding(&self, layer: &LayerMembers) -> Option<&str> {
+        if self.layer_encodings.is_empty() {
+            return self.encoding();
+        }
+        self.layer_encodings
+            .get(&layer_name(stem(&layer.shp)))
+            .map(String::as_str)
+    }
+
+    /// The EPSG code declared by the `AUTHORITY` clause of the outermost
+    /// `PROJCS`/`GEOGCS` node of the `.prj`, if any.
+    pub fn projection_epsg(&mut self) -> Result<Option<u32>> {
+        Ok(self.projection()?.and_then(prj::epsg))
+    }
+
+    /// The coordinate system described by the `.prj`, if there is one and
+    /// it's a `PROJCS` or `GEOGCS` we can parse.
+    pub fn crs(&mut self) -> Result<Option<Crs>> {
+        Ok(self.projection()?.and_then(prj::crs))
+    }
+
+    /// The `.prj`'s coordinate system as a proj4 string, like
+    /// `+proj=longlat +datum=WGS84 +no_defs`, worked out by PROJ from the
+    /// EPSG code if there is one and from the WKT otherwise. `None` when
+    /// there's no `.prj`. Fails with `Error::ProjCreate`, which carries
+    /// PROJ's own message, when PROJ can't make sense of it (an unparseable
+    /// `.prj` and a PROJ without the database for its EPSG code look alike
+    /// otherwise), and with `Error::UnknownCrs` when PROJ has no proj4
+    /// definition for it.
+    #[cfg(feature = "proj")]
+    pub fn projection_proj4(&mut self) -> Result<Option<String>> {
+        let definition = match self.projection_epsg()? {
+            Some(epsg) => format!("EPSG:{}", epsg),
+            None => match self.projection()? {
+                Some(wkt) => wkt.to_owned(),
+                None => return Ok(None),
+            },
+        };
+        let definition = proj::Proj::new(&definition)?.def()?;
+        // PROJ hands definitions back without the leading `+`s
+        let proj4 = definition
+            .split_whitespace()
+            .map(|param| format!("+{}", param.trim_start_matches('+')))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if proj4.is_empty() {
+            return Err(Error::UnknownCrs);
+        }
+        Ok(Some(proj4))
+    }
+
+    /// Which components the default layer has, without reading any of
+    /// them.
+    pub fn components(&self) -> Result<LayerComponents> {
+        let layer = self.default_layer()?;
+        Ok(LayerComponents {
+            stem: layer_name(stem(&layer.shp)),
+            has_shx: layer.shx.is_some(),
+            has_dbf: layer.dbf.is_some(),
+            has_prj: self.projection.is_some(),
+            has_cpg: self.has_cpg,
+        })
+    }
+
+    fn default_layer(&self) -> Result<LayerMembers> {
+        self.default
+            .clone()
+            .ok_or(Error::MultipleFilesFound(".shp"))
+    }
+
+    /// The 100-byte header of the default layer's `.shp`, without reading
+    /// any further into it.
+    fn header(&mut self) -> Result<Header> {
+        let layer = self.default_layer()?;
+        let head = self
+            .container
+            .read_member_head(&layer.shp, 100)
+            .map_err(|err| member_error(&layer.shp, err))?;
+        Ok(Header::read_from(&mut &head[..])?)
+    }
+
+    /// The default layer's `.shp` header exactly as stored, for getting at
+    /// fields like the file length and version that `bbox()` and friends
+    /// don't cover. Only the header gets decompressed. Fails with
+    /// `Error::ShpHeaderTooShort` when the `.shp` is shorter than that.
+    pub fn shp_header_bytes(&mut self) -> Result<[u8; 100]> {
+        let layer = self.default_layer()?;
+        let head = self
+            .container
+            .read_member_head(&layer.shp, 100)
+            .map_err(|err| member_error(&layer.shp, err))?;
+        head[..]
+            .try_into()
+            .map_err(|_| Error::ShpHeaderTooShort(head.len()))
+    }
+
+    /// The file code starting the `.shp` header, which is always 9994 in a
+    /// real shapefile; anything else means the `.shp` is corrupt or not a
+    /// shapefile at all.
+    pub fn shp_file_code(&mut self) -> Result<i32> {
+        let header = self.shp_header_bytes()?;
+        Ok(i32::from_be_bytes([
+            header[0], header[1], header[2], header[3],
+        ]))
+    }
+
+    /// The version in the `.shp` header, which is 1000 for every shapefile
+    /// written to the spec.
+    pub fn shp_version(&mut self) -> Result<i32> {
+        let header = self.shp_header_bytes()?;
+        Ok(i32::from_le_bytes([
+            header[28], header[29], header[30], header[31],
+        ]))
+    }
+
+    /// Whether the `.shp` header says the layer holds multipatches, which
+    /// most conversions can't handle; only the header is read.
+    pub fn contains_multipatch(&mut self) -> Result<bool> {
+        Ok(self.header()?.shape_type == shapefile::ShapeType::Multipatch)
+    }
+
+    /// The extent of every shape, as recorded in the `.shp` header. Only the
+    /// header is read, so this is cheap even for huge files. The `z` and `m`
+    /// ranges are zero for shape types without them. Layers without any
+    /// shapes have no extent, and get whatever the writer put there (usually
+    /// zeros), so check `is_empty()` first; some writers get it wrong even
+    /// then, which `computed_bounds()` works around.
+    pub fn bbox(&mut self) -> Result<BBoxZ> {
+        Ok(self.header()?.bbox)
+    }
+
+    /// The `[min_x, min_y, max_x, max_y]` extent of every coordinate of
+    /// every shape, for when the header's `bbox()` can't be trusted. This
+    /// reads through the whole `.shp`. Null shapes don't count, so this is
+    /// `None` when there are only those, or no shapes at all.
+    pub fn computed_bounds(&mut self) -> Result<Option<[f64; 4]>> {
+        let mut extent = None;
+        for shape in self.shape_reader()?.iter_shapes() {
+            extend_extent(&mut extent, &shape?);
+        }
+        Ok(extent)
+    }
+
+    /// How many shapes of each type the layer actually holds, by `Shape`
+    /// variant name (`"Point"`, `"NullShape"` and so on). The header only
+    /// names one type, but files mixing several do turn up.
+    pub fn shape_type_counts(&mut self) -> Result<HashMap<String, usize>> {
+        let mut counts = HashMap::new();
+        for shape in self.shape_reader()?.iter_shapes() {
+            *counts.entry(variant_name(&shape?).to_owned()).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    /// The dimension of the layer's shape type, from the `.shp` header.
+    /// The `Z` types always have room for `m` values but needn't use it, so
+    /// they're `Xyz` unless the header's `m` range holds actual data.
+    pub fn dimension(&mut self) -> Result<Dimension> {
+        let header = self.header()?;
+        let (has_z, has_m) = (header.shape_type.has_z(), header.shape_type.has_m());
+        let BBoxZ { min, max } = header.bbox;
+        let m_range_used = min.m > shapefile::NO_DATA && !(min.m == 0.0 && max.m == 0.0);
+        Ok(match (has_z, has_m) {
+            (true, true) if m_range_used => Dimension::Xyzm,
+            (true, _) => Dimension::Xyz,
+            (false, true) => Dimension::Xym,
+            (false, false) => Dimension::Xy,
+        })
+    }
+
+    /// The number of shapes according to the `.shx`, which holds one
+    /// fixed-size entry per shape; this only looks at the member's size, so
+    /// nothing gets decompressed. Without a `.shx`, one gets rebuilt from the
+    /// `.shp`, which does mean reading all of it (once).
+    pub fn shape_count(&mut self) -> Result<usize> {
+        let layer = self.default_layer()?;
+        let size = match &layer.shx {
+            Some(shx) if !self.tolerate_stale_shx => self
+                .container
+                .member_size(shx)
+                .map_err(|err| member_error(shx, err))?,
+            _ => self.layer_shx(&layer)?.get_ref().len() as u64,
+        };
+        match size.checked_sub(100) {
+            Some(records) if records % 8 == 0 => Ok((records / 8) as usize),
+            _ => Err(Error::InvalidShxSize(size)),
+        }
+    }
+
+    /// The combined size of the default layer's `.shp`, `.shx`, `.dbf`,
+    /// memo file and `.prj` once decompressed, as declared by the container
+    /// (the zip's central directory, say) when it was opened, so nothing
+    /// gets decompressed. With several layers, it's the total for all of
+    /// them.
+    pub fn uncompressed_size(&self) -> u64 {
+        self.uncompressed_size
+    }
+
+    /// The number of records according to the `.dbf` header, reading only
+    /// the start of it. `None` when there's no `.dbf`. Comparing this to
+    /// `shape_count()` catches layers whose shapes and records don't match.
+    pub fn record_count(&mut self) -> Result<Option<usize>> {
+        let dbf = match self.default_layer()?.dbf {
+            Some(dbf) => dbf,
+            None => return Ok(None),
+        };
+        let head = self
+            .container
+            .read_member_head(&dbf, 8)
+            .map_err(|err| member_error(&dbf, err))?;
+        let count = head
+            .get(4..8)
+            .and_then(|count| count.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or_else(|| {
+                member_error(&dbf, io::Error::from(io::ErrorKind::UnexpectedEof).into())
+            })?;
+        Ok(Some(count as usize))
+    }
+
+    /// Checks that the default layer has as many records as shapes, since
+    /// `reader()` and friends quietly stop at whichever runs out first. A
+    /// layer without a `.dbf` has nothing to disagree with.
+    pub fn validate(&mut self) -> Result<()> {
+        let dbf = match self.record_count()? {
+            Some(dbf) => dbf,
+            None => return Ok(()),
+        };
+        let shp = self.len()?;
+        if shp != dbf {
+            return Err(Error::RecordCountMismatch { shp, dbf });
+        }
+        Ok(())
+    }
+
+    /// The number of shapes, from the `.shx` when there is one and by
+    /// reading through the `.shp` otherwise. This counts shapes, not DBF
+    /// records, should the two disagree.
+    pub fn len(&mut self) -> Result<usize> {
+        self.shape_count()
+    }
+
+    pub fn is_empty(&mut self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// The layer's `.shx`, rebuilt from the `.shp` (just once) if it has
+    /// none, or if it's stale and that's tolerated.
+    fn layer_shx(&mut self, layer: &LayerMembers) -> Result<Buffer> {
+        match &layer.shx {
+            Some(shx) if !self.tolerate_stale_shx => return self.read_member(shx),
+            _ => {}
+        }
+        if let Some(index) = self.indexes.get(&layer.shp) {
+            return Ok(Cursor::new(index.clone()));
+        }
+        if let Some(shx) = &layer.shx {
+            let shx = self.read_member(shx)?;
+            let shp_len = self.container.member_size(&layer.shp);
+            let shp_len = shp_len.map_err(|err| member_error(&layer.shp, err))?;
+            if shx::fits(shx.get_ref(), shp_len) {
+                return Ok(shx);
+            }
+            self.found_stale_shx = true;
+        }
+        let index: Arc<[u8]> = shx::build(&mut self.read_member(&layer.shp)?)?.into();
+        self.indexes.insert(layer.shp.clone(), index.clone());
+        Ok(Cursor::new(index))
+    }
+
+    fn layer_shape_reader(&mut self, layer: &LayerMembers) -> Result<ShapeReader<Buffer>> {
+        let shp_reader = self.read_member(&layer.shp)?;
+        let shx_reader = self.layer_shx(layer)?;
+        Ok(ShapeReader::with_shx(shp_reader, shx_reader)?)
+    }
+
+    fn layer_dbf_reader(&mut self, layer: &LayerMembers) -> Result<Option<dbase::Reader<Buffer>>> {
+        match &layer.dbf {
+            Some(dbf) => {
+                let codepage = self.dbf_encoding(layer).and_then(cpg::codepage);
+                let dbf = self.read_member(dbf)?;
+                let memo = match &layer.memo {
+                    Some(memo) => Some(self.read_member(memo)?),
+                    None => None,
+                };
+                Ok(Some(cpg::dbase_reader(dbf, memo, codepage)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn layer_reader(&mut self, layer: &LayerMembers) -> Result<Reader<Buffer, Buffer>> {
+        let dbf = match self.layer_dbf_reader(layer)? {
+            Some(dbf) => dbf,
+            None => {
+                let shx = self.layer_shx(layer)?.into_inner();
+                empty_dbf(shx.len().saturating_sub(100) / 8)?
+            }
+        };
+        let shp = self.layer_shape_reader(layer)?;
+        Ok(Reader::new(shp, dbf))
+    }
+
+    pub fn shape_reader(&mut self) -> Result<ShapeReader<Buffer>> {
+        let layer = self.default_layer()?;
+        self.layer_shape_reader(&layer)
+    }
+
+    /// The shape at `index`, found through the `.shx` so that none of the
+    /// shapes before it have to be parsed; `None` when `index` is past the
+    /// end. Layers without a `.shx` get one rebuilt first.
+    pub fn shape_at(&mut self, index: usize) -> Result<Option<Shape>> {
+        Ok(self.shape_reader()?.read_nth_shape(index).transpose()?)
+    }
+
+    /// Like `shape_reader()`, but without ever holding more than the spill
+    /// threshold of the `.shp` (or `.shx`) in memory: bigger members are
+    /// copied out to a temporary file, which is deleted once the reader is
+    /// dropped. A missing `.shx` is rebuilt by scanning the spooled `.shp`,
+    /// and not cached.
+    #[cfg(feature = "streaming")]
+    pub fn shape_reader_streaming(&mut self) -> Result<ShapeReader<SpooledTempFile>> {
+        let layer = self.default_layer()?;
+        let mut shp_reader = self.spool_member(&layer.shp)?;
+        Ok(if let Some(shx) = &layer.shx {
+            ShapeReader::with_shx(shp_reader, self.spool_member(shx)?)
+        } else {
+            let index = shx::build(&mut shp_reader)?;
+            ShapeReader::with_shx(shp_reader, &index[..])
+        }?)
+    }
+
+    pub fn dbf_reader(&mut self) -> Result<Option<dbase::Reader<Buffer>>> {
+        let layer = self.default_layer()?;
+        self.layer_dbf_reader(&layer)
+    }
+
+    /// A reader for shapes along with their records. Layers without a
+    /// `.dbf` still get one, with an empty record for every shape.
+    pub fn reader(&mut self) -> Result<Reader<Buffer, Buffer>> {
+        let layer = self.default_layer()?;
+        self.layer_reader(&layer)
+    }
+
+    /// Every shape along with its DBF record, in order; records are empty
+    /// when there's no `.dbf`.
+    pub fn iter(&mut self) -> Result<impl Iterator<Item = Result<(Shape, Record)>> + '_> {
+        let reader = self.reader()?;
+        Ok(self
+            .iterated
+            .insert(reader)
+            .iter_shapes_and_records()
+            .map(|pair| Ok(pair?)))
+    }
+
+    /// Every shape, in order, without the records: unlike `iter()`, this
+    /// works whether or not there's a `.dbf`, which never gets read.
+    pub fn iter_shapes_only(&mut self) -> Result<impl Iterator<Item = Result<Shape>> + '_> {
+        let reader = self.shape_reader()?;
+        Ok(self
+            .iterated_shapes
+            .insert(reader)
+            .iter_shapes()
+            .map(|shape| Ok(shape?)))
+    }
+
+    /// Like `iter_shapes_only()`, but only the shapes of type `ty`; errors
+    /// are passed through. Types have to match exactly, so asking for
+    /// `ShapeType::Polygon` leaves out `PolygonZ`s and `PolygonM`s (ask for
+    /// each of those separately), and anything but `ShapeType::NullShape`
+    /// leaves out null shapes.
+    pub fn shapes_of_type(
+        &mut self,
+        ty: shapefile::ShapeType,
+    ) -> Result<impl Iterator<Item = Result<Shape>> + '_> {
+        Ok(self
+            .iter_shapes_only()?
+            .filter(move |shape| shape.as_ref().map_or(true, |shape| shape_type(shape) == ty)))
+    }
+
+    /// Every DBF record, in order, as text keyed by field name: values are
+    /// written out as `to_csv()` does, with trailing spaces trimmed and
+    /// missing values left empty. Only the `.dbf` gets read.
+    pub fn iter_attributes(
+        &mut self,
+    ) -> Result<impl Iterator<Item = Result<BTreeMap<String, String>>> + '_> {
+        let reader = self.dbf_reader()?.ok_or(Error::NoDbfFound)?;
+        Ok(self
+            .iterated_records
+            .insert(reader)
+            .iter_records()
+            .map(|record| {
+                Ok(record?
+                    .into_iter()
+                    .map(|(name, value)| {
+                        let value = csv::field_value(&value).trim_end().to_owned();
+                        (name, value)
+                    })
+                    .collect())
+            }))
+    }
+
+    /// Like `iter()`, but with each shape's zero-based index in the layer,
+    /// which stays attached through any filtering done afterwards.
+    pub fn enumerate_shapes(
+        &mut self,
+    ) -> Result<impl Iterator<Item = Result<(usize, Shape, Record)>> + '_> {
+        Ok(self
+            .iter()?
+            .enumerate()
+            .map(|(index, pair)| pair.map(|(shape, record)| (index, shape, record))))
+    }
+
+    /// Like `iter()`, but with only the named fields left in each record.
+    /// `dbase` has no way of skipping fields, so every field still gets
+    /// parsed; the rest are dropped straight after. Fails with
+    /// `Error::FieldNotFound` if the `.dbf` has no such field.
+    pub fn reader_with_fields(
+        &mut self,
+        fields: &[&str],
+    ) -> Result<impl Iterator<Item = Result<(Shape, Record)>> + '_> {
+        let reader = self.dbf_reader()?.ok_or(Error::NoDbfFound)?;
+        let known: HashSet<_> = reader.fields().iter().map(|field| field.name()).collect();
+        if let Some(missing) = fields.iter().find(|field| !known.contains(**field)) {
+            return Err(Error::FieldNotFound((*missing).to_owned()));
+        }
+        let wanted: HashSet<String> = fields.iter().map(|field| (*field).to_owned()).collect();
+        Ok(self.iter()?.map(move |pair| {
+            pair.map(|(shape, mut record)| {
+                record.as_mut().retain(|name, _| wanted.contains(name));
+                (shape, record)
+            })
+        }))
+    }
+
+    /// Like `iter()`, but only the pairs whose record satisfies `predicate`;
+    /// errors are passed through. Every shape still gets read (the `.shp`
+    /// can't be skipped through), but only matching ones are handed out.
+    pub fn filter<'a, F>(
+        &'a mut self,
+        mut predicate: F,
+    ) -> Result<impl Iterator<Item = Result<(Shape, Record)>> + 'a>
+    where
+        F: FnMut(&Record) -> bool + 'a,
+    {
+        Ok(self.iter()?.filter(move |pair| match pair {
+            Ok((_, record)) => predicate(record),
+            Err(_) => true,
+        }))
+    }
+
+    /// Like `iter()`, but with each record deserialized into a `T`, whose
+    /// serde field names (after any `rename`) are matched against the DBF
+    /// field names. Values are handed over as `field_value_to_json()` gives
+    /// them, so dates are strings and missing values are `None`. A record
+    /// that doesn't fit `T` yields `Error::Deserialize` for its index, and
+    /// iteration carries on with the next one.
+    #[cfg(feature = "serde")]
+    pub fn iter_as<T>(&mut self) -> Result<impl Iterator<Item = Result<(Shape, T)>> + '_>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        Ok(self.iter()?.enumerate().map(|(index, pair)| {
+            let (shape, record) = pair?;
+            let record = serde_json::Value::Object(json::record_to_json(record));
+            let record = serde_json::from_value(record)
+                .map_err(|source| Error::Deserialize { index, source })?;
+            Ok((shape, record))
+        }))
+    }
+
+    /// Like `iter()`, but without the `NullShape`s. Their records are
+    /// skipped along with them, so every shape still comes with its own
+    /// record.
+    pub fn iter_non_null(&mut self) -> Result<impl Iterator<Item = Result<(Shape, Record)>> + '_> {
+        Ok(self
+            .iter()?
+            .filter(|pair| !matches!(pair, Ok((Shape::NullShape, _)))))
+    }
+
+    /// An R-tree of every shape's bounding box, for spatial queries that
+    /// have to be fast. The boxes are the ones the `.shp` records store, as
+    /// `feature_bboxes()` reads them, so no shape gets decoded. Null shapes
+    /// are left out, so the tree can hold fewer entries than there are
+    /// shapes.
+    #[cfg(feature = "rstar")]
+    pub fn build_rtree(&mut self) -> Result<rstar::RTree<IndexedGeometry>> {
+        let entries = self
+            .feature_bboxes()?
+            .into_iter()
+            .enumerate()
+            .filter(|(_, bbox)| !bbox.iter().any(|value| value.is_nan()))
+            .map(|(index, bbox)| IndexedGeometry { index, bbox })
+            .collect();
+        Ok(rstar::RTree::bulk_load(entries))
+    }
+
+    /// The shapes whose bounding box overlaps `bbox`, given as `[min_x,
+    /// min_y, max_x, max_y]`. Shapes merely touching its edge are included;
+    /// null shapes never are. This compares bounding boxes only, so a shape
+    /// can be yielded without itself intersecting `bbox`. Shapes are read
+    /// one at a time, as for `iter_shapes_only()`, and errors are passed
+    /// through.
+    pub fn shapes_in_bbox(
+        &mut self,
+        bbox: [f64; 4],
+    ) -> Result<impl Iterator<Item = Result<Shape>> + '_> {
+        Ok(self.iter_shapes_only()?.filter(move |shape| {
+            shape.as_ref().map_or(true, |shape| {
+                shape_bbox(shape).map_or(false, |extent| bboxes_overlap(extent, bbox))
+            })
+        }))
+    }
+
+    /// The number of points in each shape, in order, counting every part
+    /// and ring: 1 for point shapes and 0 for null shapes. Nothing gets
+    /// converted, so this is cheaper than counting `geometries()`.
+    pub fn vertex_counts(&mut self) -> Result<Vec<usize>> {
+        self.iter_shapes_only()?
+            .map(|shape| Ok(vertex_count(&shape?)))
+            .collect()
+    }
+
+    /// The number of parts in each shape, in order: line strings for
+    /// polylines, rings (holes included) for polygons and patches for
+    /// multipatches. A point is 1 part and a multipoint has one per point;
+    /// null shapes have none. Anything above 1 makes for a multipart
+    /// geometry once converted.
+    pub fn part_counts(&mut self) -> Result<Vec<usize>> {
+        self.iter_shapes_only()?
+            .map(|shape| Ok(part_count(&shape?)))
+            .collect()
+    }
+
+    /// The `[min_x, min_y, max_x, max_y]` bounding box of each shape, in
+    /// order, as the `.shp` records store them, without decoding any
+    /// points; a point's box is just the point. Null shapes have no box,
+    /// so theirs is all `NaN`s, which keeps the boxes lined up with the
+    /// shapes.
+    pub fn feature_bboxes(&mut self) -> Result<Vec<[f64; 4]>> {
+        let layer = self.default_layer()?;
+        let mut shp = self.read_member(&layer.shp)?;
+        records::bboxes(&mut shp)
+    }
+
+    /// The stems of every `.shp` in the container, in order. These include
+    /// the directory, as in `data/2023/parcels`, always separated by slashes
+    /// even if the container uses backslashes.
+    pub fn layers(&self) -> Vec<String> {
+        self.layers.keys().cloned().collect()
+    }
+
+    /// The layers whose stem matches the glob `pattern`, like
+    /// `tl_2023_*_roads`, in order. Case is ignored, as everywhere else
+    /// stems are compared, and `*` matches across slashes, so a pattern
+    /// without a directory still needs a leading `*/` or `**/` to match
+    /// layers inside one.
+    #[cfg(feature = "glob")]
+    pub fn layers_matching(&self, pattern: &str) -> Result<Vec<String>> {
+        let pattern = glob::Pattern::new(pattern)?;
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..glob::MatchOptions::new()
+        };
+        Ok(self
+            .layers
+            .keys()
+            .filter(|layer| pattern.matches_with(layer, options))
+            .cloned()
+            .collect())
+    }
+
+    /// A reader for the shapefile whose `.shp` has the given stem, along with
+    /// the `.shx` and `.dbf` sharing that stem.
+    pub fn layer(&mut self, stem: &str) -> Result<Reader<Buffer, Buffer>> {
+        let layer = self
+            .layers
+            .get(&layer_name(stem))
+            .cloned()
+            .ok_or_else(|| Error::LayerNotFound(stem.to_owned()))?;
+        self.layer_reader(&layer)
+    }
+
+    /// Reads the whole default layer into memory: every shape and record,
+    /// the `.prj` and the schema, none of it tied to the container. Meant
+    /// for layers small enough to hold at once and queried repeatedly.
+    pub fn load(&mut self) -> Result<Layer> {
+        let features = self.iter()?.collect::<Result<Vec<_>>>()?;
+        let projection = self.projection()?.map(str::to_owned);
+        let schema = self.schema()?;
+        Ok(Layer::new(features, projection, schema))
+    }
+
+    /// The fields of the default layer's `.dbf`, in order; `None` when
+    /// there's no `.dbf`.
+    pub fn schema(&mut self) -> Result<Option<Vec<FieldInfo>>> {
+        let layer = self.default_layer()?;
+        let reader = match self.layer_dbf_reader(&layer)? {
+            Some(reader) => reader,
+            None => return Ok(None),
+        };
+        let dbf = match &layer.dbf {
+            Some(dbf) => self.read_member(dbf)?.into_inner(),
+            None => return Ok(None),
+        };
+        Ok(Some(
+            reader
+                .fields()
+                .iter()
+                .enumerate()
+                .map(|(index, field)| FieldInfo::new(field, schema::decimal_count(&dbf, index)))
+                .collect(),
+        ))
+    }
+
+    /// Writes the default layer into the GeoPackage at `out` (creating it if
+    /// need be) as the feature table `table_name`. The CRS goes along when
+    /// the `.prj` names an EPSG code, and the table is marked as having an
+    /// undefined one otherwise. Point and multipoint layers get `POINT` and
+    /// `MULTIPOINT` geometry columns, and the rest `GEOMETRY`, since
+    /// single-part lines and polygons get written as such.
+    ///
+    /// After an `fid` primary key and the `geom` column, each DBF field
+    /// becomes a column of this type:
+    ///
+    /// | DBF type                   | GeoPackage type |
+    /// |----------------------------|-----------------|
+    /// | `Character`, `Memo`        | `TEXT`          |
+    /// | `Numeric` without decimals | `INTEGER`       |
+    /// | `Numeric` with decimals    | `DOUBLE`        |
+    /// | `Float`                    | `FLOAT`         |
+    /// | `Double`, `Currency`       | `DOUBLE`        |
+    /// | `Integer`                  | `MEDIUMINT`     |
+    /// | `Logical`                  | `BOOLEAN`       |
+    /// | `Date`                     | `DATE`          |
+    /// | `DateTime`                 | `DATETIME`      |
+    ///
+    /// Missing values are `NULL`, as are the geometries of null shapes. A
+    /// field named `fid` or `geom` (in any case) would clash with the
+    /// table's own columns, and fails with `ReservedColumnName`.
+    #[cfg(feature = "geopackage")]
+    pub fn to_geopackage<P>(&mut self, out: P, table_name: &str) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        use shapefile::ShapeType;
+
+        let geometry_type = match self.header()?.shape_type {
+            ShapeType::Point | ShapeType::PointM | ShapeType::PointZ => "POINT",
+            ShapeType::Multipoint | ShapeType::MultipointM | ShapeType::MultipointZ => "MULTIPOINT",
+            _ => "GEOMETRY",
+        };
+        let fields = self.schema()?.unwrap_or_default();
+        let wkt = self.projection()?.map(str::to_owned);
+        let srs = self.projection_epsg()?.zip(wkt.as_deref());
+        let table = geopackage::Table {
+            name: table_name,
+            geometry_type,
+            srs,
+            extent: self.computed_bounds()?,
+            fields: &fields,
+        };
+        if self.default_layer()?.dbf.is_none() {
+            let shapes = self.shape_reader()?.read()?;
+            let rows = shapes
+                .into_iter()
+                .map(|shape| Ok((shape, Record::default())));
+            geopackage::write(out, &table, rows)
+        } else {
+            geopackage::write(out, &table, self.iter()?)
+        }
+    }
+
+    /// Every shape and record as a single Arrow record batch: a column per
+    /// DBF field, then, if `with_geometry`, a `geometry` column holding each
+    /// shape as WKB (as `to_wkb()` writes it), which is null for null
+    /// shapes. Without it only the `.dbf` gets read, so a layer whose
+    /// shapes can't be converted (like multipatches) still gives up its
+    /// attributes. A field named `geometry` fails with
+    /// `ReservedColumnName`, rather than making two columns of that name.
+    ///
+    /// | DBF type                                     | Arrow type          |
+    /// |----------------------------------------------|---------------------|
+    /// | `Character`, `Memo`                          | `Utf8`              |
+    /// | `Numeric`, `Float`, `Double`, `Currency`     | `Float64`           |
+    /// | `Integer`                                    | `Int32`             |
+    /// | `Logical`                                    | `Boolean`           |
+    /// | `Date`                                       | `Date32`            |
+    /// | `DateTime`                                   | `Timestamp(Second)` |
+    ///
+    /// Missing values are null. Everything is held in memory at once; see
+    /// `arrow_batches()` for large layers.
+    #[cfg(feature = "arrow")]
+    pub fn to_arrow(&mut self, with_geometry: bool) -> Result<arrow::record_batch::RecordBatch> {
+        let fields = self.schema()?.unwrap_or_default();
+        let schema = arrow_batch::schema(&fields, with_geometry)?;
+        let rows = self
+            .column_rows(with_geometry)?
+            .collect::<Result<Vec<_>>>()?;
+        arrow_batch::batch(&schema, Columns::new(&fields, rows, with_geometry)?)
+    }
+
+    /// Like `to_arrow()`, but `batch_size` rows at a time (the last batch
+    /// can be shorter), so only one batch is ever held in memory. A row
+    /// that can't be read fails the batch it would have been in.
+    #[cfg(feature = "arrow")]
+    pub fn arrow_batches(
+        &mut self,
+        batch_size: usize,
+        with_geometry: bool,
+    ) -> Result<impl Iterator<Item = Result<arrow::record_batch::RecordBatch>> + '_> {
+        let fields = self.schema()?.unwrap_or_default();
+        let schema = arrow_batch::schema(&fields, with_geometry)?;
+        let mut rows = self.column_rows(with_geometry)?;
+        let batch_size = batch_size.max(1);
+        Ok(std::iter::from_fn(move || {
+            let mut batch = Vec::new();
+            for row in rows.by_ref().take(batch_size) {
+                match row {
+                    Ok(row) => batch.push(row),
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+            if batch.is_empty() {
+                return None;
+            }
+            let columns = Columns::new(&fields, batch, with_geometry);
+            Some(columns.and_then(|columns| arrow_batch::batch(&schema, columns)))
+        }))
+    }
+
+    /// The rows for `to_arrow()` and `to_polars()`: each record along with its shape, or with
+    /// `None` (and the `.shp` left unread) when the geometries aren't
+    /// wanted.
+    #[cfg(any(feature = "arrow", feature = "polars"))]
+    fn column_rows(
+        &mut self,
+        with_geometry: bool,
+    ) -> Result<Box<dyn Iterator<Item = Result<columns::Row>> + '_>> {
+        if with_geometry {
+            let rows = self.iter()?;
+            Ok(Box::new(rows.map(|row| {
+                row.map(|(shape, record)| (Some(shape), record))
+            })))
+        } else {
+            let records = self.iter_records()?;
+            Ok(Box::new(
+                records.map(|record| record.map(|record| (None, record))),
+            ))
+        }
+    }
+
+    /// Every DBF record, in order, without reading the `.shp`; records are
+    /// empty when there's no `.dbf`.
+    #[cfg(any(feature = "arrow", feature = "polars"))]
+    fn iter_records(&mut self) -> Result<impl Iterator<Item = Result<Record>> + '_> {
+        let reader = match self.dbf_reader()? {
+            Some(reader) => reader,
+            None => empty_dbf(self.len()?)?,
+        };
+        Ok(self
+            .iterated_records
+            .insert(reader)
+            .iter_records()
+            .map(|record| Ok(record?)))
+    }
+
+    /// Every shape and record as a Polars data frame: a series per DBF
+    /// field, then, if `with_geometry`, a `geometry` series of WKB (as
+    /// `to_wkb()` writes it), null for null shapes. `Character` and `Memo`
+    /// fields become `String` series, `Integer`s `Int32`, other numbers
+    /// `Float64`, `Logical`s `Boolean`, `Date`s `Date` and `DateTime`s
+    /// millisecond `Datetime`s; missing values are null. As with
+    /// `to_arrow()`, leaving the geometries out means only the `.dbf` gets
+    /// read, and a field named `geometry` fails with `ReservedColumnName`
+    /// when they're in.
+    #[cfg(feature = "polars")]
+    pub fn to_polars(&mut self, with_geometry: bool) -> Result<polars::frame::DataFrame> {
+        let fields = self.schema()?.unwrap_or_default();
+        columns::check_names(&fields, with_geometry)?;
+        let rows = self
+            .column_rows(with_geometry)?
+            .collect::<Result<Vec<_>>>()?;
+        polars_frame::data_frame(&fields, Columns::new(&fields, rows, with_geometry)?)
+    }
+
+    /// Writes the default layer's records as CSV, with a header row of field
+    /// names; the shapes are left out.
+    pub fn to_csv<W>(&mut self, out: W) -> Result<()>
+    where
+        W: Write,
+    {
+        let mut reader = self.dbf_reader()?.ok_or(Error::NoDbfFound)?;
+        let names: Vec<_> = reader
+            .fields()
+            .iter()
+            .map(|field| field.name().to_owned())
+            .collect();
+        csv::write(out, &names, reader.iter_records().map(|record| Ok(record?)))
+    }
+
+    /// The smallest and largest values of a numeric field, reading only the
+    /// `.dbf`. `None` when every value is missing.
+    pub fn numeric_range(&mut self, field: &str) -> Result<Option<(f64, f64)>> {
+        let mut reader = self.dbf_reader()?.ok_or(Error::NoDbfFound)?;
+        let info = reader
+            .fields()
+            .iter()
+            .find(|info| info.name() == field)
+            .ok_or_else(|| Error::FieldNotFound(field.to_owned()))?;
+        match info.field_type() {
+            FieldType::Numeric
+            | FieldType::Float
+            | FieldType::Double
+            | FieldType::Integer
+            | FieldType::Currency => {}
+            _ => return Err(Error::FieldNotNumeric(field.to_owned())),
+        }
+
+        let mut range: Option<(f64, f64)> = None;
+        for record in reader.iter_records() {
+            let value = match record?.remove(field) {
+                Some(FieldValue::Numeric(Some(value)))
+                | Some(FieldValue::Double(value))
+                | Some(FieldValue::Currency(value)) => value,
+                Some(FieldValue::Float(Some(value))) => f64::from(value),
+                Some(FieldValue::Integer(value)) => f64::from(value),
+                _ => continue,
+            };
+            range = Some(match range {
+                Some((min, max)) => (min.min(value), max.max(value)),
+                None => (value, value),
+            });
+        }
+        Ok(range)
+    }
+
+    /// How many records have each value of `field`, reading only the
+    /// `.dbf`. Values are counted as text, written out as `to_csv()` does
+    /// and with any trailing spaces trimmed, so missing values are counted
+    /// as `""`.
+    pub fn value_counts(&mut self, field: &str) -> Result<HashMap<String, usize>> {
+        let mut reader = self.dbf_reader()?.ok_or(Error::NoDbfFound)?;
+        if !reader.fields().iter().any(|info| info.name() == field) {
+            return Err(Error::FieldNotFound(field.to_owned()));
+        }
+        let mut counts = HashMap::new();
+        for record in reader.iter_records() {
+            let value = record?.remove(field).map(|value| csv::field_value(&value));
+            let value = value.as_deref().unwrap_or_default().trim_end();
+            *counts.entry(value.to_owned()).or_default() += 1;
+        }
+        Ok(counts)
+    }
+
+    /// The name and type of every field, as in `schema()`.
+    pub fn types(&mut self) -> Result<Option<Vec<(String, String)>>> {
+        Ok(self.schema()?.map(|fields| {
+            fields
+                .into_iter()
+                .map(|field| (field.name, field.field_type.to_string()))
+                .collect()
+        }))
+    }
+
+    /// Converts every shape into a `geo_types::Geometry`. Null shapes yield
+    /// `Error::GeometryConversion`, and multipatches of triangle strips or
+    /// fans, which have no equivalent either, `Error::UnsupportedShapeType`.
+    /// Shapes are read and converted one at a time, as for
+    /// `iter_shapes_only()`.
+    #[cfg(feature = "geo-types")]
+    pub fn geometries(
+        &mut self,
+    ) -> Result<impl Iterator<Item = Result<geo_types::Geometry<f64>>> + '_> {
+        Ok(self
+            .iter_shapes_only()?
+            .map(|shape| shape.and_then(shape_geometry)))
+    }
+
+    /// Every polygon ring that isn't closed or encloses no area, for
+    /// rejecting (or repairing) layers before they get into spatial
+    /// operations that assume valid geometries. Only the `.shp` is read.
+    #[cfg(feature = "geo")]
+    pub fn validate_geometries(&mut self) -> Result<Vec<GeometryIssue>> {
+        validate::issues(self.iter_shapes_only()?, false)
+    }
+
+    /// Like `validate_geometries()`, but also finding rings whose edges
+    /// cross or overlap, which takes longer.
+    #[cfg(feature = "geo")]
+    pub fn validate_geometries_with_self_intersections(&mut self) -> Result<Vec<GeometryIssue>> {
+        validate::issues(self.iter_shapes_only()?, true)
+    }
+
+    /// Like `geometries()`, but with polygons following the OGC convention
+    /// of counter-clockwise outer rings and clockwise holes, rather than
+    /// the shapefile one. Each hole goes to the smallest outer ring around
+    /// it, even when the record lists it elsewhere; `geometries()` just
+    /// gives holes to the outer ring before them. Shapes are read one at a
+    /// time, as for `geometries()`.
+    #[cfg(feature = "geo")]
+    pub fn geometries_ogc_winding(
+        &mut self,
+    ) -> Result<impl Iterator<Item = Result<geo_types::Geometry<f64>>> + '_> {
+        Ok(self.iter_shapes_only()?.map(|shape| {
+            let shape = shape?;
+            let polygons = match &shape {
+                Shape::Polygon(polygon) => winding::multi_polygon(polygon.rings()),
+                Shape::PolygonM(polygon) => winding::multi_polygon(polygon.rings()),
+                Shape::PolygonZ(polygon) => winding::multi_polygon(polygon.rings()),
+                _ => return shape_geometry(shape),
+            };
+            Ok(geo_types::Geometry::MultiPolygon(polygons))
+        }))
+    }
+
+    /// The area of every shape, in the square units of the layer's CRS (so
+    /// square degrees for longitude/latitude): reprojecting to an equal-area
+    /// CRS first is up to the caller. Null shapes and anything but polygons
+    /// get an area of zero, keeping the areas in step with the shapes.
+    #[cfg(feature = "geo")]
+    pub fn areas(&mut self) -> Result<Vec<f64>> {
+        self.measure(measure::area)
+    }
+
+    /// The length of every shape, in the units of the layer's CRS. Polygons
+    /// get their perimeter, holes included; null shapes and points get zero.
+    #[cfg(feature = "geo")]
+    pub fn lengths(&mut self) -> Result<Vec<f64>> {
+        self.measure(measure::length)
+    }
+
+    /// Like `geometries()`, but with lines and polygons simplified by
+    /// Douglas–Peucker, leaving out vertices that are less than `epsilon`
+    /// (in the units of the layer's CRS) from the line through their
+    /// neighbours. Points pass through unchanged. Large tolerances can leave
+    /// polygons invalid, with rings that cross or collapse.
+    #[cfg(feature = "geo")]
+    pub fn simplified_geometries(
+        &mut self,
+        epsilon: f64,
+    ) -> Result<impl Iterator<Item = Result<geo_types::Geometry<f64>>> + '_> {
+        Ok(self
+            .geometries()?
+            .map(move |geometry| Ok(measure::simplify(geometry?, epsilon))))
+    }
+
+    /// The centroid of every shape, or `None` for null shapes and empty
+    /// geometries. For polygons this is the area-weighted centre, which can
+    /// fall outside oddly shaped ones.
+    #[cfg(feature = "geo")]
+    pub fn centroids(&mut self) -> Result<Vec<Option<geo_types::Point<f64>>>> {
+        self.measure(measure::centroid)
+    }
+
+    /// `measure` applied to every shape, with null shapes getting the
+    /// default.
+    #[cfg(feature = "geo")]
+    fn measure<T>(&mut self, measure: fn(&geo_types::Geometry<f64>) -> T) -> Result<Vec<T>>
+    where
+        T: Default,
+    {
+        self.shape_reader()?
+            .read()?
+            .into_iter()
+            .map(|shape| match shape {
+                Shape::NullShape => Ok(T::default()),
+                shape => shape_geometry(shape).map(|geometry| measure(&geometry)),
+            })
+            .collect()
+    }
+
+    /// Like `geometries()`, but reprojected to WGS 84 longitude/latitude
+    /// from the CRS named by the `.prj`'s EPSG code. Fails with
+    /// `Error::UnknownCrs` when there's no such code, rather than passing the
+    /// coordinates through untouched.
+    #[cfg(feature = "proj")]
+    pub fn geometries_wgs84(
+        &mut self,
+    ) -> Result<impl Iterator<Item = Result<geo_types::Geometry<f64>>> + '_> {
+        use proj::{Proj, Transform};
+
+        let epsg = self.projection_epsg()?.ok_or(Error::UnknownCrs)?;
+        let proj = Proj::new_known_crs(&format!("EPSG:{}", epsg), "EPSG:4326", None)?;
+        Ok(self.geometries()?.map(move |geometry| {
+            let mut geometry = geometry?;
+            geometry.transform(&proj)?;
+            Ok(geometry)
+        }))
+    }
+
+    /// Writes a copy of the default layer into a new zip archive at `out`,
+    /// reprojected from the CRS named by the `.prj`'s EPSG code to
+    /// `target_epsg`, with a `.prj` holding the (ESRI flavoured) WKT of the
+    /// target. Only x/y coordinates are transformed; z and m values, null
+    /// shapes and the records are copied over as they are, though a memo
+    /// file and `.cpg` aren't. Fails with `Error::UnknownCrs` when there's no
+    /// source EPSG code.
+    #[cfg(feature = "proj")]
+    pub fn reproject_to<W>(&mut self, out: W, target_epsg: u32) -> Result<()>
+    where
+        W: Write + Seek,
+    {
+        use proj::{wkt::WktVersion, Proj};
+
+        let source = self.projection_epsg()?.ok_or(Error::UnknownCrs)?;
+        let target = format!("EPSG:{}", target_epsg);
+        let proj = Proj::new_known_crs(&format!("EPSG:{}", source), &target, None)?;
+        let wkt = Proj::new(&target)?.as_wkt(Some(WktVersion::Wkt1_Esri), None)?;
+
+        let layer = self.default_layer()?;
+        let mut shp = self.read_member(&layer.shp)?.into_inner().to_vec();
+        reproject::shp(&mut shp, |x, y| Ok(proj.convert((x, y))?))?;
+        let shx = self.layer_shx(&layer)?.into_inner();
+        let dbf = match &layer.dbf {
+            Some(dbf) => self.read_member(dbf)?.into_inner().to_vec(),
+            None => empty_dbf_contents(shx.len().saturating_sub(100) / 8)?,
+        };
+
+        let name = layer_name(stem(&layer.shp));
+        let name = name.rsplit('/').next().unwrap_or_default();
+        let mut writer = ZippedShapefileWriter::new(out)
+            .with_name(name)
+            .with_projection(wkt);
+        writer.write(&shp, &shx, &dbf)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    /// Like `geometries()`, but with the conversions spread over rayon's
+    /// thread pool. The `.shp` still gets read one shape at a time, and
+    /// every shape is held in memory (alongside its geometry, once
+    /// converted) until all are done, so this trades memory for speed.
+    #[cfg(feature = "rayon")]
+    pub fn par_geometries(&mut self) -> Result<Vec<Result<geo_types::Geometry<f64>>>> {
+        use rayon::prelude::*;
+
+        Ok(self
+            .shape_reader()?
+            .read()?
+            .into_par_iter()
+            .map(shape_geometry)
+            .collect())
+    }
+
+    /// Encodes every shape as (little-endian, 2D) Well-Known Binary. Shapes
+    /// that `geometries()` can't convert yield the same errors here.
+    #[cfg(feature = "wkb")]
+    pub fn to_wkb(&mut self) -> Result<impl Iterator<Item = Result<Vec<u8>>> + '_> {
+        Ok(self
+            .geometries()?
+            .map(|geometry| geometry.map(|geometry| wkb::geometry(&geometry))))
+    }
+
+    /// Writes every shape as Well-Known Text. Multipart polylines and
+    /// polygons become `MULTILINESTRING`s and `MULTIPOLYGON`s, parts-less
+    /// ones are `EMPTY`, and null shapes are `GEOMETRYCOLLECTION EMPTY`.
+    /// Shapes are read one at a time, as for `geometries()`.
+    #[cfg(feature = "wkt")]
+    pub fn wkt_strings(&mut self) -> Result<impl Iterator<Item = Result<String>> + '_> {
+        Ok(self.iter_shapes_only()?.map(|shape| match shape? {
+            Shape::NullShape => Ok(wkt::NULL.to_owned()),
+            shape => shape_geometry(shape).map(|geometry| wkt::geometry(&geometry)),
+        }))
+    }
+
+    /// Writes every shape and its record to `out` as newline-delimited
+    /// GeoJSON features, one line at a time: unlike `to_geojson()`, only one
+    /// feature is ever held in memory.
+    #[cfg(feature = "geojson")]
+    pub fn write_ndgeojson<W>(&mut self, mut out: W) -> Result<()>
+    where
+        W: Write,
+    {
+        for pair in self.iter()? {
+            let (shape, record) = pair?;
+            serde_json::to_writer(&mut out, &json::feature(shape, record)?)
+                .map_err(io::Error::from)?;
+            out.write_all(b"\n")?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Converts the layer into a GeoJSON feature collection, with each
+    /// record's fields as the feature's properties. Null shapes and missing
+    /// DBF values become JSON `null`s.
+    #[cfg(feature = "geojson")]
+    pub fn to_geojson(&mut self) -> Result<geojson::FeatureCollection> {
+        let features = self
+            .iter()?
+            .map(|pair| pair.and_then(|(shape, record)| json::feature(shape, record)))
+            .collect::<Result<_>>()?;
+        Ok(geojson::FeatureCollection {
+            bbox: None,
+            features,
+            foreign_members: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::{write::FileOptions, ZipWriter};
+
+    fn zip_of(members: &[(&str, &[u8])]) -> Cursor<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in members {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        let mut archive = writer.finish().unwrap();
+        archive.set_position(0);
+        archive
+    }
+
+    /// A `.shp` holding one record per entry of `shape_types`, each being an
+    /// empty shape of that type code (only `0`, the null shape, is valid).
+    fn shp_of(shape_types: &[i32]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&9994i32.to_be_bytes());
+        buf.extend_from_slice(&[0; 20]);
+        let length = 100 + 12 * shape_types.len() as i32;
+        buf.extend_from_slice(&(length / 2).to_be_bytes());
+        buf.extend_from_slice(&1000i32.to_le_bytes());
+        buf.extend_from_slice(&0i32.to_le_bytes());
+        buf.extend_from_slice(&[0; 64]);
+        for (index, shape_type) in shape_types.iter().enumerate() {
+            buf.extend_from_slice(&(index as i32 + 1).to_be_bytes());
+            buf.extend_from_slice(&2i32.to_be_bytes());
+            buf.extend_from_slice(&shape_type.to_le_bytes());
+        }
+        buf
+    }
+
+    fn points_shp(points: &[shapefile::Point]) -> Vec<u8> {
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(points)
+            .unwrap();
+        shp.into_inner()
+    }
+
+    /// A `.dbf` with a single `NAME` character field, one record per name.
+    fn names_dbf<E: dbase::Encoding + 'static>(names: &[&str], encoding: E) -> Vec<u8> {
+        let mut dbf = Cursor::new(Vec::new());
+        let mut writer = dbase::TableWriterBuilder::with_encoding(encoding)
+            .add_character_field("NAME".try_into().unwrap(), 32)
+            .build_with_dest(&mut dbf);
+        for name in names {
+            let mut record = dbase::Record::default();
+            record.insert(
+                "NAME".to_owned(),
+                FieldValue::Character(Some(name.to_string())),
+            );
+            writer.write_record(&record).unwrap();
+        }
+        drop(writer);
+        dbf.into_inner()
+    }
+
+    /// A fresh directory under the system temp dir holding `members`.
+    fn dir_of(name: &str, members: &[(&str, &[u8])]) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("zipped-shapefile-{}-{}", name, std::process::id()));
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir(&dir).unwrap();
+        for (name, contents) in members {
+            std::fs::write(dir.join(name), contents).unwrap();
+        }
+        dir
+    }
+
+    fn names(zipped: &mut ZippedShapefile<Cursor<Vec<u8>>>) -> Vec<FieldValue> {
+        let mut dbf = zipped.dbf_reader().unwrap().unwrap();
+        dbf.read()
+            .unwrap()
+            .into_iter()
+            .map(|mut record| record.remove("NAME").unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn it_works() {
+        let result = 2 + 2;
+        assert_eq!(result, 4);
+    }
+
+    #[test]
+    fn missing_shp() {
+        let archive = zip_of(&[("a.dbf", b"")]);
+        assert!(matches!(
+            ZippedShapefile::new(archive),
+            Err(Error::NoShpFound)
+        ));
+    }
+
+    #[test]
+    fn corrupt_archive() {
+        let archive = zip_of(&[("a.shp", &shp_of(&[0]))]).into_inner();
+        let truncated = &archive[..archive.len() - 10];
+        assert!(matches!(
+            ZippedShapefile::from_bytes(truncated),
+            Err(Error::CorruptArchive(_))
+        ));
+    }
+
+    #[test]
+    fn falsified_member_size() {
+        let shp = shp_of(&[0, 0]);
+        let archive = zip_of(&[("a.shp", &shp)]).into_inner();
+        // the uncompressed size, in both the local and the central header
+        let falsified = |size: u32| {
+            let mut archive = archive.clone();
+            for (signature, at) in [(b"PK\x03\x04", 22), (b"PK\x01\x02", 24)] {
+                let header = archive
+                    .windows(4)
+                    .position(|window| window == signature)
+                    .unwrap();
+                archive[header + at..header + at + 4].copy_from_slice(&size.to_le_bytes());
+            }
+            archive
+        };
+        for size in [shp.len() - 8, shp.len() + 8] {
+            let archive = falsified(size as u32);
+            let mut zipped = ZippedShapefile::from_bytes(&archive[..]).unwrap();
+            assert!(matches!(
+                zipped.shape_reader(),
+                Err(Error::CorruptArchive(message)) if message.starts_with("a.shp")
+            ));
+        }
+    }
+
+    #[test]
+    fn split_archive() {
+        let archive = zip_of(&[
+            ("a.shp", &shp_of(&[0, 0])),
+            ("a.dbf", &names_dbf(&["x", "y"], dbase::UnicodeLossy)),
+        ])
+        .into_inner();
+        // split right before the central directory, as `zip -s` might have,
+        // with the split signature starting the first volume
+        let eocd = archive.len() - 22;
+        let u32_at = |at: usize| u32::from_le_bytes(archive[at..at + 4].try_into().unwrap());
+        let directory = u32_at(eocd + 16) as usize;
+        let mut first = b"PK\x07\x08".to_vec();
+        first.extend_from_slice(&archive[..directory]);
+        let mut last = archive[directory..].to_vec();
+        let mut at = 0;
+        while last[at..].starts_with(b"PK\x01\x02") {
+            let offset = u32::from_le_bytes(last[at + 42..at + 46].try_into().unwrap());
+            last[at + 42..at + 46].copy_from_slice(&(offset + 4).to_le_bytes());
+            let lengths = [28, 30, 32]
+                .map(|field| u16::from_le_bytes([last[at + field], last[at + field + 1]]) as usize);
+            at += 46 + lengths.iter().sum::<usize>();
+        }
+        let eocd = last.len() - 22;
+        last[eocd + 4..eocd + 8].copy_from_slice(&[1, 0, 1, 0]);
+        last[eocd + 16..eocd + 20].copy_from_slice(&[0; 4]);
+
+        let dir = dir_of("split_archive", &[("a.z01", &first), ("a.zip", &last)]);
+        let mut zipped =
+            ZippedShapefile::open_volumes([dir.join("a.z01"), dir.join("a.zip")]).unwrap();
+        assert_eq!(zipped.iter().unwrap().count(), 2);
+        assert!(matches!(
+            ZippedShapefile::open(dir.join("a.zip")),
+            Err(Error::SplitArchiveUnsupported)
+        ));
+        assert!(matches!(
+            ZippedShapefile::open_volumes([dir.join("a.zip")]),
+            Err(Error::CorruptArchive(_))
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn debug() {
+        let archive = zip_of(&[
+            ("a.shp", &shp_of(&[0])),
+            ("a.dbf", &names_dbf(&["x"], dbase::UnicodeLossy)),
+            ("readme.txt", b"hi"),
+        ]);
+        let zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(
+            format!("{:?}", zipped),
+            r#"Shapefile { shp: Some("a.shp"), shx: None, dbf: Some("a.dbf"), has_prj: false, layers: 1, members: 3 }"#
+        );
+    }
+
+    #[test]
+    fn directory_entries() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for dir in ["data/", "data/roads.shp/"] {
+            writer.add_directory(dir, FileOptions::default()).unwrap();
+        }
+        writer
+            .start_file("data/rivers.shp", FileOptions::default())
+            .unwrap();
+        writer.write_all(&shp_of(&[0])).unwrap();
+        let zipped = ZippedShapefile::new(writer.finish().unwrap()).unwrap();
+        assert_eq!(zipped.layers(), vec!["data/rivers"]);
+        assert_eq!(zipped.members(), vec!["data/rivers.shp"]);
+    }
+
+    #[test]
+    fn shape_reader() {
+        let shp = points_shp(&[shapefile::Point::new(1.0, 2.0)]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        let shapes = zipped.shape_reader().unwrap().read().unwrap();
+        assert!(matches!(shapes[..], [Shape::Point(p)] if p == shapefile::Point::new(1.0, 2.0)));
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0, 0]))])).unwrap();
+        let shapes = zipped.shape_reader().unwrap().read().unwrap();
+        assert!(matches!(shapes[..], [Shape::NullShape, Shape::NullShape]));
+    }
+
+    #[cfg(feature = "streaming")]
+    #[test]
+    fn shape_reader_streaming() {
+        let points = [
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &points_shp(&points))])).unwrap();
+        assert_eq!(zipped.spill_threshold(), DEFAULT_SPILL_THRESHOLD);
+        assert!(!zipped.spool_member("a.shp").unwrap().is_rolled());
+
+        zipped.set_spill_threshold(0);
+        assert!(zipped.spool_member("a.shp").unwrap().is_rolled());
+        let shapes = zipped.shape_reader_streaming().unwrap().read().unwrap();
+        assert!(matches!(
+            shapes[..],
+            [Shape::Point(a), Shape::Point(b)] if [a, b] == points
+        ));
+    }
+
+    #[test]
+    fn bbox() {
+        let points = [
+            shapefile::Point::new(1.0, 4.0),
+            shapefile::Point::new(3.0, 2.0),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &points_shp(&points))])).unwrap();
+        let bbox = zipped.bbox().unwrap();
+        assert_eq!((bbox.min.x, bbox.min.y), (1.0, 2.0));
+        assert_eq!((bbox.max.x, bbox.max.y), (3.0, 4.0));
+        assert_eq!((bbox.min.z, bbox.max.z), (0.0, 0.0));
+
+        let truncated = points_shp(&points)[..60].to_vec();
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &truncated)])).unwrap();
+        assert!(matches!(zipped.bbox(), Err(Error::Shapefile(_))));
+    }
+
+    #[test]
+    fn shapes_in_bbox() {
+        let points = [
+            shapefile::Point::new(0.0, 0.0),
+            shapefile::Point::new(5.0, 5.0),
+            shapefile::Point::new(10.0, 10.0),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &points_shp(&points))])).unwrap();
+        let found = zipped
+            .shapes_in_bbox([5.0, 5.0, 8.0, 8.0])
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert!(matches!(found[..], [Shape::Point(p)] if p == points[1]));
+
+        let square = shapefile::Polygon::new(shapefile::PolygonRing::Outer(vec![
+            shapefile::Point::new(0.0, 0.0),
+            shapefile::Point::new(0.0, 1.0),
+            shapefile::Point::new(1.0, 1.0),
+            shapefile::Point::new(1.0, 0.0),
+            shapefile::Point::new(0.0, 0.0),
+        ]));
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[square])
+            .unwrap();
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", shp.get_ref())])).unwrap();
+        assert_eq!(
+            zipped.shapes_in_bbox([1.0, 0.5, 2.0, 2.0]).unwrap().count(),
+            1
+        );
+        assert_eq!(
+            zipped.shapes_in_bbox([1.5, 0.5, 2.0, 2.0]).unwrap().count(),
+            0
+        );
+        assert_eq!(
+            zipped
+                .shapes_in_bbox([-1.0, -1.0, 3.0, 3.0])
+                .unwrap()
+                .count(),
+            1
+        );
+
+        // a truncated record comes out as an error instead of being dropped
+        let shp = points_shp(&points);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp[..shp.len() - 4])])).unwrap();
+        let found: Vec<_> = zipped
+            .shapes_in_bbox([-1.0, -1.0, 20.0, 20.0])
+            .unwrap()
+            .collect();
+        assert!(matches!(found[..], [Ok(_), Ok(_), Err(_)]));
+    }
+
+    #[test]
+    fn computed_bounds() {
+        let points = [
+            shapefile::Point::new(1.0, 4.0),
+            shapefile::Point::new(-3.0, 2.0),
+        ];
+        let mut shp = points_shp(&points);
+        // as left by a careless producer
+        shp[36..68].fill(0);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        assert_eq!(zipped.bbox().unwrap().max.x, 0.0);
+        assert_eq!(
+            zipped.computed_bounds().unwrap(),
+            Some([-3.0, 2.0, 1.0, 4.0])
+        );
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0, 0]))])).unwrap();
+        assert_eq!(zipped.computed_bounds().unwrap(), None);
+    }
+
+    #[test]
+    fn shape_type_counts() {
+        // a null shape followed by two point records
+        let mut shp = shp_of(&[0]);
+        for number in 2i32..4 {
+            shp.extend_from_slice(&number.to_be_bytes());
+            shp.extend_from_slice(&10i32.to_be_bytes());
+            shp.extend_from_slice(&1i32.to_le_bytes());
+            shp.extend_from_slice(&[0; 16]);
+        }
+        let length = (shp.len() / 2) as i32;
+        shp[24..28].copy_from_slice(&length.to_be_bytes());
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        let counts = zipped.shape_type_counts().unwrap();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["NullShape"], 1);
+        assert_eq!(counts["Point"], 2);
+    }
+
+    #[test]
+    fn dimension() {
+        let dimension = |shp: &[u8]| {
+            ZippedShapefile::new(zip_of(&[("a.shp", shp)]))
+                .unwrap()
+                .dimension()
+                .unwrap()
+        };
+        assert_eq!(dimension(&shp_of(&[0])), Dimension::Xy);
+        assert_eq!(
+            dimension(&points_shp(&[shapefile::Point::new(1.0, 2.0)])),
+            Dimension::Xy
+        );
+
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[shapefile::PointM::new(1.0, 2.0, 3.0)])
+            .unwrap();
+        assert_eq!(dimension(shp.get_ref()), Dimension::Xym);
+
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[shapefile::PointZ::new(1.0, 2.0, 3.0, 4.0)])
+            .unwrap();
+        assert_eq!(dimension(shp.get_ref()), Dimension::Xyzm);
+
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[shapefile::PointZ::new(1.0, 2.0, 3.0, shapefile::NO_DATA)])
+            .unwrap();
+        assert_eq!(dimension(shp.get_ref()), Dimension::Xyz);
+    }
+
+    #[test]
+    fn shp_header_bytes() {
+        let shp = shp_of(&[0, 0]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        let header = zipped.shp_header_bytes().unwrap();
+        assert_eq!(header[..], shp[..100]);
+        assert_eq!(i32::from_be_bytes(header[24..28].try_into().unwrap()), 62);
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp[..60])])).unwrap();
+        assert!(matches!(
+            zipped.shp_header_bytes(),
+            Err(Error::ShpHeaderTooShort(60))
+        ));
+    }
+
+    #[test]
+    fn shp_file_code() {
+        let mut shp = shp_of(&[0]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        assert_eq!(zipped.shp_file_code().unwrap(), 9994);
+        assert_eq!(zipped.shp_version().unwrap(), 1000);
+
+        shp[..4].copy_from_slice(b"PK\x03\x04");
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        assert_eq!(zipped.shp_file_code().unwrap(), 0x504b_0304);
+    }
+
+    #[test]
+    fn shapes_of_type() {
+        use shapefile::ShapeType;
+
+        // no writer will mix types, so the records are put together by hand
+        let mut shp = shp_of(&[]);
+        let contents: [&[f64]; 4] = [&[1.0, 2.0], &[], &[3.0, 4.0, 5.0, 0.0], &[6.0, 7.0]];
+        for (index, (shape_type, coords)) in [1, 0, 11, 1].iter().zip(contents).enumerate() {
+            shp.extend_from_slice(&(index as i32 + 1).to_be_bytes());
+            shp.extend_from_slice(&(2 + 4 * coords.len() as i32).to_be_bytes());
+            shp.extend_from_slice(&i32::to_le_bytes(*shape_type));
+            for coord in coords {
+                shp.extend_from_slice(&coord.to_le_bytes());
+            }
+        }
+        // multipoints are easier to get from a writer, as the records of a
+        // file of their own
+        let mut multipoints = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut multipoints)
+            .write_shapes(&[shapefile::MultipointM::new(vec![shapefile::PointM::new(
+                1.0, 2.0, 3.0,
+            )])])
+            .unwrap();
+        shp.extend_from_slice(&multipoints.get_ref()[100..]);
+        let mut multipoints = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut multipoints)
+            .write_shapes(&[shapefile::MultipointZ::new(vec![shapefile::PointZ::new(
+                1.0, 2.0, 3.0, 4.0,
+            )])])
+            .unwrap();
+        shp.extend_from_slice(&multipoints.get_ref()[100..]);
+        let length = shp.len() as i32 / 2;
+        shp[24..28].copy_from_slice(&length.to_be_bytes());
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        let count = |zipped: &mut ZippedShapefile<_>, ty| {
+            zipped
+                .shapes_of_type(ty)
+                .unwrap()
+                .map(|shape| assert_eq!(shape_type(&shape.unwrap()), ty))
+                .count()
+        };
+        assert_eq!(count(&mut zipped, ShapeType::Point), 2);
+        assert_eq!(count(&mut zipped, ShapeType::PointZ), 1);
+        assert_eq!(count(&mut zipped, ShapeType::NullShape), 1);
+        assert_eq!(count(&mut zipped, ShapeType::Polygon), 0);
+        assert_eq!(count(&mut zipped, ShapeType::Multipoint), 0);
+        assert_eq!(count(&mut zipped, ShapeType::MultipointM), 1);
+        assert_eq!(count(&mut zipped, ShapeType::MultipointZ), 1);
+    }
+
+    #[test]
+    fn stale_shx() {
+        let shx = shx::build(&mut Cursor::new(shp_of(&[0; 3]))).unwrap();
+        let members = [("a.shp", &shp_of(&[0; 2])[..]), ("a.shx", &shx)];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert!(!zipped.tolerate_stale_shx());
+        assert_eq!(zipped.len().unwrap(), 3);
+        assert!(zipped.shape_at(2).is_err());
+        assert!(!zipped.found_stale_shx());
+
+        zipped.set_tolerate_stale_shx(true);
+        assert_eq!(zipped.len().unwrap(), 2);
+        assert_eq!(zipped.shape_reader().unwrap().read().unwrap().len(), 2);
+        assert!(zipped.shape_at(2).unwrap().is_none());
+        assert!(zipped.found_stale_shx());
+
+        let shx = shx::build(&mut Cursor::new(shp_of(&[0; 2]))).unwrap();
+        let members = [("a.shp", &shp_of(&[0; 2])[..]), ("a.shx", &shx)];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        zipped.set_tolerate_stale_shx(true);
+        assert_eq!(zipped.len().unwrap(), 2);
+        assert!(!zipped.found_stale_shx());
+    }
+
+    #[test]
+    fn shape_count() {
+        let points = [shapefile::Point::new(1.0, 2.0); 3];
+        let mut shp = Cursor::new(Vec::new());
+        let mut shx = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::with_shx(&mut shp, &mut shx)
+            .write_shapes(&points)
+            .unwrap();
+        let (shp, shx) = (shp.into_inner(), shx.into_inner());
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp), ("a.shx", &shx)])).unwrap();
+        assert_eq!(zipped.shape_count().unwrap(), 3);
+        assert_eq!(zipped.len().unwrap(), 3);
+        assert!(!zipped.is_empty().unwrap());
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        assert_eq!(zipped.shape_count().unwrap(), 3);
+        assert_eq!(zipped.len().unwrap(), 3);
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[]))])).unwrap();
+        assert!(zipped.is_empty().unwrap());
+
+        let archive = zip_of(&[("a.shp", &shp), ("a.shx", &shx[..104])]);
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        assert!(matches!(
+            zipped.shape_count(),
+            Err(Error::InvalidShxSize(104))
+        ));
+    }
+
+    #[test]
+    fn empty_shp() {
+        let members = [
+            ("a.shp", &shp_of(&[])[..]),
+            ("a.dbf", &names_dbf(&[], dbase::UnicodeLossy)),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.shape_count().unwrap(), 0);
+        assert_eq!(zipped.len().unwrap(), 0);
+        assert!(zipped.is_empty().unwrap());
+        assert!(zipped.reader().unwrap().read().unwrap().is_empty());
+        assert_eq!(zipped.iter().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn iter() {
+        let dbf = names_dbf(&["a", "b"], dbase::UnicodeLossy);
+        let archive = zip_of(&[("a.shp", &shp_of(&[0, 0])), ("a.dbf", &dbf)]);
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        let names = zipped
+            .iter()
+            .unwrap()
+            .map(|pair| {
+                let (shape, mut record) = pair.unwrap();
+                assert!(matches!(shape, Shape::NullShape));
+                record.remove("NAME").unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                FieldValue::Character(Some("a".to_owned())),
+                FieldValue::Character(Some("b".to_owned()))
+            ]
+        );
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0, 0]))])).unwrap();
+        let records = zipped
+            .iter()
+            .unwrap()
+            .map(|pair| pair.unwrap().1)
+            .collect::<Vec<_>>();
+        assert_eq!(records, vec![Record::default(), Record::default()]);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn new_async() {
+        let archive = zip_of(&[("a.shp", &shp_of(&[0, 0]))]).into_inner();
+        let mut zipped = ZippedShapefile::new_async(&archive[..]).await.unwrap();
+        assert_eq!(zipped.shape_reader().unwrap().read().unwrap().len(), 2);
+
+        assert!(matches!(
+            ZippedShapefile::new_async(&b"not a zip"[..]).await,
+            Err(Error::CorruptArchive(_))
+        ));
+    }
+
+    #[test]
+    fn filter() {
+        let members = [
+            ("a.shp", &shp_of(&[0, 0, 0])[..]),
+            (
+                "a.dbf",
+                &names_dbf(&["R1", "C2", "R1"], dbase::UnicodeLossy),
+            ),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let r1 = FieldValue::Character(Some("R1".to_owned()));
+        let matching = zipped
+            .filter(move |record| record.get("NAME") == Some(&r1))
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(matching.len(), 2);
+        assert_eq!(zipped.filter(|_| false).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn reader_with_fields() {
+        let mut dbf = Cursor::new(Vec::new());
+        let mut writer = dbase::TableWriterBuilder::new()
+            .add_character_field("NAME".try_into().unwrap(), 8)
+            .add_character_field("ZONE".try_into().unwrap(), 8)
+            .add_numeric_field("AREA".try_into().unwrap(), 10, 2)
+            .build_with_dest(&mut dbf);
+        let mut record = dbase::Record::default();
+        record.insert("NAME".to_owned(), FieldValue::Character(None));
+        record.insert(
+            "ZONE".to_owned(),
+            FieldValue::Character(Some("R1".to_owned())),
+        );
+        record.insert("AREA".to_owned(), FieldValue::Numeric(Some(1.5)));
+        writer.write_record(&record).unwrap();
+        drop(writer);
+        let members = [("a.shp", &shp_of(&[0])[..]), ("a.dbf", dbf.get_ref())];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+
+        let pairs = zipped
+            .reader_with_fields(&["ZONE", "AREA"])
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let mut fields: Vec<_> = pairs[0].1.clone().into_iter().collect();
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            fields,
+            vec![
+                ("AREA".to_owned(), FieldValue::Numeric(Some(1.5))),
+                (
+                    "ZONE".to_owned(),
+                    FieldValue::Character(Some("R1".to_owned()))
+                ),
+            ]
+        );
+        assert!(matches!(
+            zipped.reader_with_fields(&["ZONE", "OWNER"]),
+            Err(Error::FieldNotFound(name)) if name == "OWNER"
+        ));
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn geometries_ogc_winding() {
+        use geo::winding_order::{Winding, WindingOrder};
+
+        let polygon = shapefile::Polygon::with_rings(vec![shapefile::PolygonRing::Outer(vec![
+            shapefile::Point::new(0.0, 0.0),
+            shapefile::Point::new(0.0, 2.0),
+            shapefile::Point::new(2.0, 2.0),
+            shapefile::Point::new(2.0, 0.0),
+            shapefile::Point::new(0.0, 0.0),
+        ])]);
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[polygon])
+            .unwrap();
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", shp.get_ref())])).unwrap();
+        let geometries = zipped
+            .geometries_ogc_winding()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        match &geometries[..] {
+            [geo_types::Geometry::MultiPolygon(polygons)] => assert_eq!(
+                polygons.0[0].exterior().winding_order(),
+                Some(WindingOrder::CounterClockwise)
+            ),
+            _ => panic!("expected a single multipolygon, got {:?}", geometries),
+        }
+
+        // a truncated record comes out as an error instead of failing the lot
+        let shp = shp.into_inner();
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp[..shp.len() - 4])])).unwrap();
+        let mut geometries = zipped.geometries_ogc_winding().unwrap();
+        assert!(matches!(geometries.next(), Some(Err(_))));
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn simplified_geometries() {
+        let points = [shapefile::Point::new(1.0, 2.0)];
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &points_shp(&points))])).unwrap();
+        let simplified: Vec<_> = zipped
+            .simplified_geometries(100.0)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(simplified, vec![geo_types::Point::new(1.0, 2.0).into()]);
+    }
+
+    #[cfg(feature = "geo")]
+    #[test]
+    fn measures() {
+        let members = [("a.shp", &shp_of(&[0, 0])[..])];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.areas().unwrap(), vec![0.0, 0.0]);
+        assert_eq!(zipped.lengths().unwrap(), vec![0.0, 0.0]);
+        assert_eq!(zipped.centroids().unwrap(), vec![None, None]);
+
+        let shp = points_shp(&[shapefile::Point::new(1.0, 2.0)]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        assert_eq!(zipped.areas().unwrap(), vec![0.0]);
+        assert_eq!(zipped.lengths().unwrap(), vec![0.0]);
+        assert_eq!(
+            zipped.centroids().unwrap(),
+            vec![Some(geo_types::Point::new(1.0, 2.0))]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn iter_as() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Place {
+            #[serde(rename = "NAME")]
+            name: String,
+            population: Option<u32>,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        struct Numbered {
+            #[serde(rename = "NAME")]
+            _name: f64,
+        }
+
+        let members = [
+            ("a.shp", &shp_of(&[0, 0])[..]),
+            ("a.dbf", &names_dbf(&["a", "b"], dbase::UnicodeLossy)),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let places: Vec<_> = zipped
+            .iter_as::<Place>()
+            .unwrap()
+            .map(|pair| pair.unwrap().1)
+            .collect();
+        assert_eq!(
+            places,
+            ["a", "b"].map(|name| Place {
+                name: name.to_owned(),
+                population: None
+            })
+        );
+
+        let pairs: Vec<_> = zipped.iter_as::<Numbered>().unwrap().collect();
+        assert!(matches!(
+            pairs[..],
+            [
+                Err(Error::Deserialize { index: 0, .. }),
+                Err(Error::Deserialize { index: 1, .. })
+            ]
+        ));
+    }
+
+    #[test]
+    fn iter_shapes_only() {
+        let points = [
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ];
+        let members = [("a.shp", &points_shp(&points)[..]), ("a.dbf", b"not a dbf")];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let shapes: Vec<_> = zipped
+            .iter_shapes_only()
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert!(
+            matches!(shapes[..], [Shape::Point(a), Shape::Point(b)] if a == points[0] && b == points[1])
+        );
+
+        let mut zipped = ZippedShapefile::new(zip_of(&members[..1])).unwrap();
+        assert_eq!(zipped.iter_shapes_only().unwrap().count(), 2);
+    }
+
+    #[test]
+    fn enumerate_shapes() {
+        let members = [
+            ("a.shp", &shp_of(&[0, 0, 0])[..]),
+            ("a.dbf", &names_dbf(&["a", "b", "c"], dbase::UnicodeLossy)),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let kept: Vec<_> = zipped
+            .enumerate_shapes()
+            .unwrap()
+            .map(Result::unwrap)
+            .filter(|(_, _, record)| {
+                record.get("NAME") != Some(&FieldValue::Character(Some("b".to_owned())))
+            })
+            .map(|(index, _, _)| index)
+            .collect();
+        assert_eq!(kept, vec![0, 2]);
+    }
+
+    #[test]
+    fn iter_non_null() {
+        let members = [
+            ("a.shp", &shp_of(&[0, 0])[..]),
+            (
+                "a.dbf",
+                &names_dbf(&["deleted", "kept"], dbase::UnicodeLossy),
+            ),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.iter_non_null().unwrap().count(), 0);
+
+        // a null shape followed by a point record
+        let mut shp = shp_of(&[0]);
+        shp.extend_from_slice(&2i32.to_be_bytes());
+        shp.extend_from_slice(&10i32.to_be_bytes());
+        shp.extend_from_slice(&1i32.to_le_bytes());
+        shp.extend_from_slice(&1.0f64.to_le_bytes());
+        shp.extend_from_slice(&2.0f64.to_le_bytes());
+        let length = (shp.len() / 2) as i32;
+        shp[24..28].copy_from_slice(&length.to_be_bytes());
+        let members = [
+            ("a.shp", &shp[..]),
+            (
+                "a.dbf",
+                &names_dbf(&["deleted", "kept"], dbase::UnicodeLossy),
+            ),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.iter().unwrap().count(), 2);
+        let pairs = zipped
+            .iter_non_null()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert!(matches!(pairs[0].0, Shape::Point(p) if p == shapefile::Point::new(1.0, 2.0)));
+        assert_eq!(
+            pairs[0].1.get("NAME"),
+            Some(&FieldValue::Character(Some("kept".to_owned())))
+        );
+    }
+
+    #[test]
+    fn max_member_size() {
+        let shp = shp_of(&[0, 0]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        assert_eq!(zipped.max_member_size(), None);
+
+        zipped.set_max_member_size(shp.len() as u64);
+        assert!(zipped.shape_reader().is_ok());
+        zipped.set_max_member_size(shp.len() as u64 - 1);
+        assert!(matches!(
+            zipped.shape_reader(),
+            Err(Error::MemberSizeTooLarge(size)) if size == shp.len() as u64
+        ));
+        #[cfg(feature = "streaming")]
+        assert!(matches!(
+            zipped.shape_reader_streaming(),
+            Err(Error::MemberSizeTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn max_compression_ratio() {
+        let shp = shp_of(&[0]);
+        let prj = vec![b' '; 100_000];
+        let members = [("a.shp", &shp[..]), ("a.prj", &prj)];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.max_compression_ratio(), None);
+        zipped.set_max_compression_ratio(100.0);
+        assert!(zipped.shape_reader().is_ok());
+
+        let mut zipped = ZippedShapefileBuilder::new()
+            .max_compression_ratio(100.0)
+            .build(zip_of(&members))
+            .unwrap();
+        assert!(matches!(
+            zipped.projection(),
+            Err(Error::SuspiciousCompressionRatio { name, ratio })
+                if name == "a.prj" && ratio > 100.0
+        ));
+
+        let members = [("a.shp", &shp_of(&[0; 10_000])[..])];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        zipped.set_max_compression_ratio(2.0);
+        assert!(matches!(
+            zipped.shape_reader(),
+            Err(Error::SuspiciousCompressionRatio { .. })
+        ));
+        #[cfg(feature = "streaming")]
+        assert!(matches!(
+            zipped.shape_reader_streaming(),
+            Err(Error::SuspiciousCompressionRatio { .. })
+        ));
+
+        // loose files aren't compressed to begin with
+        let dir = dir_of("ratio", &members);
+        let mut unzipped = DirShapefile::open(&dir).unwrap();
+        unzipped.set_max_compression_ratio(1.0);
+        assert!(unzipped.shape_reader().is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn tar() {
+        use flate2::{write::GzEncoder, Compression};
+
+        let dbf = names_dbf(&["a", "b"], dbase::UnicodeLossy);
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, contents) in [
+            ("data/a.shp", &shp_of(&[0])[..]),
+            ("data/a.shp", &shp_of(&[0, 0])),
+            ("data/a.dbf", &dbf),
+            ("data/a.prj", b"GEOGCS[\"a\"]"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_cksum();
+            builder.append_data(&mut header, name, contents).unwrap();
+        }
+        let tar = builder.into_inner().unwrap();
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&tar).unwrap();
+        let gz = gz.finish().unwrap();
+
+        for archive in [tar, gz] {
+            let mut shapefile = TarShapefile::from_tar(&archive[..]).unwrap();
+            assert_eq!(
+                shapefile.members(),
+                vec!["data/a.shp", "data/a.dbf", "data/a.prj"]
+            );
+            assert_eq!(shapefile.projection().unwrap(), Some("GEOGCS[\"a\"]"));
+            assert_eq!(shapefile.shape_count().unwrap(), 2);
+            assert_eq!(shapefile.record_count().unwrap(), Some(2));
+            assert_eq!(shapefile.reader().unwrap().read().unwrap().len(), 2);
+            #[cfg(feature = "streaming")]
+            assert_eq!(
+                shapefile
+                    .shape_reader_streaming()
+                    .unwrap()
+                    .read()
+                    .unwrap()
+                    .len(),
+                2
+            );
+            shapefile.set_max_member_size(8);
+            assert!(matches!(
+                shapefile.dbf_reader(),
+                Err(Error::MemberSizeTooLarge(size)) if size == dbf.len() as u64
+            ));
+        }
+        assert!(TarShapefile::from_tar(&b"not a tar"[..]).is_err());
+    }
+
+    #[cfg(feature = "geopackage")]
+    #[test]
+    fn to_geopackage() {
+        let shp = points_shp(&[
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ]);
+        let dbf = names_dbf(&["a", "b"], dbase::UnicodeLossy);
+        let prj = br#"GEOGCS["NAD83",AUTHORITY["EPSG","4269"]]"#;
+        let members = [("a.shp", &shp[..]), ("a.dbf", &dbf), ("a.prj", prj)];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let dir = dir_of("geopackage", &[]);
+        let out = dir.join("out.gpkg");
+        zipped.to_geopackage(&out, "places").unwrap();
+        // a second layer can go into the same file
+        let mut zipped = ZippedShapefile::new(zip_of(&[("b.shp", &shp_of(&[0]))])).unwrap();
+        zipped.to_geopackage(&out, "nulls").unwrap();
+
+        let connection = rusqlite::Connection::open(&out).unwrap();
+        let application_id: i32 = connection
+            .query_row("PRAGMA application_id", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(application_id, 0x4750_4B47);
+        let names: Vec<String> = connection
+            .prepare("SELECT NAME FROM places ORDER BY fid")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|name| name.unwrap())
+            .collect();
+        assert_eq!(names, vec!["a", "b"]);
+        let (geometry_type, srs_id): (String, i64) = connection
+            .query_row(
+                "SELECT geometry_type_name, srs_id FROM gpkg_geometry_columns \
+                 WHERE table_name = 'places'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((geometry_type.as_str(), srs_id), ("POINT", 4269));
+        let definition: String = connection
+            .query_row(
+                "SELECT definition FROM gpkg_spatial_ref_sys WHERE srs_id = 4269",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(definition.as_bytes(), prj);
+        let max_x: f64 = connection
+            .query_row(
+                "SELECT max_x FROM gpkg_contents WHERE table_name = 'places'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(max_x, 3.0);
+        let geom: Option<Vec<u8>> = connection
+            .query_row("SELECT geom FROM nulls", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(geom, None);
+        drop(connection);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "rstar")]
+    #[test]
+    fn build_rtree() {
+        let mut shp = points_shp(&[
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ]);
+        // a null shape at the end
+        shp.extend_from_slice(&3i32.to_be_bytes());
+        shp.extend_from_slice(&2i32.to_be_bytes());
+        shp.extend_from_slice(&0i32.to_le_bytes());
+        let length = (shp.len() / 2) as i32;
+        shp[24..28].copy_from_slice(&length.to_be_bytes());
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        let tree = zipped.build_rtree().unwrap();
+        assert_eq!(tree.size(), 2);
+        let nearest = tree.nearest_neighbor(&[2.9, 4.1]).unwrap();
+        assert_eq!(
+            *nearest,
+            IndexedGeometry {
+                index: 1,
+                bbox: [3.0, 4.0, 3.0, 4.0]
+            }
+        );
+
+        // the box comes from the record, whatever its points say
+        let line = shapefile::Polyline::new(vec![
+            shapefile::Point::new(0.0, 0.0),
+            shapefile::Point::new(1.0, 1.0),
+        ]);
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[line])
+            .unwrap();
+        let mut shp = shp.into_inner();
+        // the record's max_x, after the headers, the shape type and min_x/y
+        shp[100 + 8 + 4 + 16..][..8].copy_from_slice(&5.0f64.to_le_bytes());
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        let tree = zipped.build_rtree().unwrap();
+        assert_eq!(tree.iter().next().unwrap().bbox, [0.0, 0.0, 5.0, 1.0]);
+    }
+
+    #[test]
+    fn read_sidecar() {
+        let shp = shp_of(&[0]);
+        let members = [("a.shp", &shp[..]), ("a.shp.xml", b"<metadata/>")];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.read_sidecar("a.shp.xml").unwrap(), b"<metadata/>");
+        assert_eq!(zipped.read_sidecar("a.shp").unwrap(), shp);
+        assert!(matches!(
+            zipped.read_sidecar("a.sbn"),
+            Err(Error::MemberNotFound(name)) if name == "a.sbn"
+        ));
+        zipped.set_max_member_size(4);
+        assert!(matches!(
+            zipped.read_sidecar("a.shp.xml"),
+            Err(Error::MemberSizeTooLarge(11))
+        ));
+    }
+
+    #[test]
+    fn extract_to() {
+        let shp = shp_of(&[0, 0]);
+        let dbf = names_dbf(&["a", "b"], dbase::UnicodeLossy);
+        let members = [
+            ("data/Parcels.SHP", &shp[..]),
+            ("data/Parcels.DBF", &dbf),
+            ("data/Parcels.prj", b"GEOGCS[\"a\"]"),
+        ];
+        let dir = dir_of("extract_to", &[]);
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let path = zipped.extract_to(&dir).unwrap();
+        assert_eq!(path, dir.join("Parcels.shp"));
+        let mut unpacked = DirShapefile::open(&dir).unwrap();
+        assert_eq!(
+            unpacked.members(),
+            vec!["Parcels.dbf", "Parcels.prj", "Parcels.shp", "Parcels.shx"]
+        );
+        assert_eq!(unpacked.len().unwrap(), 2);
+        assert_eq!(unpacked.projection().unwrap(), Some("GEOGCS[\"a\"]"));
+        assert_eq!(std::fs::read(dir.join("Parcels.dbf")).unwrap(), dbf);
+
+        let path = zipped.extract_with_stem(&dir, "renamed").unwrap();
+        assert_eq!(path, dir.join("renamed.shp"));
+        assert_eq!(std::fs::read(path).unwrap(), shp);
+    }
+
+    #[test]
+    fn components() {
+        let shp = shp_of(&[0]);
+        let dbf = names_dbf(&["a"], dbase::UnicodeLossy);
+        let members = [
+            ("data\\a.shp", &shp[..]),
+            ("data\\a.dbf", &dbf),
+            ("data\\a.cpg", b"UTF-8"),
+        ];
+        let zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(
+            zipped.components().unwrap(),
+            LayerComponents {
+                stem: "data/a".to_owned(),
+                has_shx: false,
+                has_dbf: true,
+                has_prj: false,
+                has_cpg: true,
+            }
+        );
+
+        let archive = zip_of(&[("a.shp", &shp), ("b.shp", &shp)]);
+        let zipped = ZippedShapefile::new(archive).unwrap();
+        assert!(matches!(
+            zipped.components(),
+            Err(Error::MultipleFilesFound(".shp"))
+        ));
+    }
+
+    #[test]
+    fn iter_attributes() {
+        let mut dbf = Cursor::new(Vec::new());
+        let mut writer = dbase::TableWriterBuilder::new()
+            .add_numeric_field("AREA".try_into().unwrap(), 10, 2)
+            .add_character_field("NAME".try_into().unwrap(), 8)
+            .add_date_field("BUILT".try_into().unwrap())
+            .build_with_dest(&mut dbf);
+        for (name, area) in [("a", Some(2.5)), ("b", None)] {
+            let mut record = dbase::Record::default();
+            record.insert("AREA".to_owned(), FieldValue::Numeric(area));
+            record.insert(
+                "NAME".to_owned(),
+                FieldValue::Character(Some(name.to_owned())),
+            );
+            record.insert(
+                "BUILT".to_owned(),
+                FieldValue::Date(Some(dbase::Date::new(31, 1, 2023))),
+            );
+            writer.write_record(&record).unwrap();
+        }
+        drop(writer);
+        let members = [("a.shp", &shp_of(&[0; 2])[..]), ("a.dbf", dbf.get_ref())];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let rows = zipped
+            .iter_attributes()
+            .unwrap()
+            .map(|row| row.unwrap().into_iter().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let row = |area: &str, name: &str| {
+            vec![
+                ("AREA".to_owned(), area.to_owned()),
+                ("BUILT".to_owned(), "2023-01-31".to_owned()),
+                ("NAME".to_owned(), name.to_owned()),
+            ]
+        };
+        assert_eq!(rows, vec![row("2.5", "a"), row("", "b")]);
+
+        let mut zipped = ZippedShapefile::new(zip_of(&members[..1])).unwrap();
+        assert!(matches!(zipped.iter_attributes(), Err(Error::NoDbfFound)));
+    }
+
+    #[test]
+    fn metadata_xml() {
+        let shp = shp_of(&[0]);
+        let members = [
+            ("a.shp", &shp[..]),
+            ("a.xml", b"<other/>"),
+            ("a.SHP.XML", b"\xef\xbb\xbf<metadata/>"),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(
+            zipped.metadata_xml().unwrap().as_deref(),
+            Some("<metadata/>")
+        );
+        // it's still a sidecar
+        let mut sidecars = zipped.sidecar_members();
+        sidecars.sort();
+        assert_eq!(sidecars, vec!["a.SHP.XML", "a.xml"]);
+
+        let mut zipped = ZippedShapefile::new(zip_of(&members[..2])).unwrap();
+        assert_eq!(zipped.metadata_xml().unwrap(), None);
+    }
+
+    #[test]
+    fn uncompressed_size() {
+        let shp = shp_of(&[0; 100]);
+        let dbf = names_dbf(&["a"], dbase::UnicodeLossy);
+        let prj = b"GEOGCS[\"a\"]";
+        let members = [
+            ("a.shp", &shp[..]),
+            ("a.dbf", &dbf),
+            ("a.prj", prj),
+            ("a.sbn", &[0; 1000]),
+        ];
+        let zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(
+            zipped.uncompressed_size(),
+            (shp.len() + dbf.len() + prj.len()) as u64
+        );
+        // nothing had to be read for that
+        assert_eq!(zipped.cache_stats(), (0, 0, 0));
+
+        let members = [
+            ("a.shp", &shp[..]),
+            ("b.shp", &shp),
+            ("b.dbf", &dbf),
+            ("project.prj", prj),
+        ];
+        let zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(
+            zipped.uncompressed_size(),
+            (2 * shp.len() + dbf.len() + prj.len()) as u64
+        );
+    }
+
+    #[test]
+    fn record_count() {
+        let members = [
+            ("a.shp", &shp_of(&[0])[..]),
+            ("a.dbf", &names_dbf(&["a", "b", "c"], dbase::UnicodeLossy)),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.record_count().unwrap(), Some(3));
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        assert_eq!(zipped.record_count().unwrap(), None);
+
+        let members = [("a.shp", &shp_of(&[0])[..]), ("a.dbf", &[3, 0, 0])];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert!(matches!(
+            zipped.record_count(),
+            Err(Error::MemberRead { name, source }) if name == "a.dbf"
+                && matches!(*source, Error::IOError(_))
+        ));
+    }
+
+    #[test]
+    fn validate() {
+        let members = [
+            ("a.shp", &shp_of(&[0, 0])[..]),
+            ("a.dbf", &names_dbf(&["a", "b"], dbase::UnicodeLossy)),
+        ];
+        assert!(ZippedShapefile::new(zip_of(&members))
+            .unwrap()
+            .validate()
+            .is_ok());
+
+        let members = [
+            ("a.shp", &shp_of(&[0, 0, 0])[..]),
+            ("a.dbf", &names_dbf(&["a", "b"], dbase::UnicodeLossy)),
+        ];
+        assert!(matches!(
+            ZippedShapefile::new(zip_of(&members)).unwrap().validate(),
+            Err(Error::RecordCountMismatch { shp: 3, dbf: 2 })
+        ));
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        assert!(zipped.validate().is_ok());
+    }
+
+    #[test]
+    fn shape_at() {
+        let points = [1.0, 2.0, 3.0].map(|x| shapefile::Point::new(x, x));
+        let mut shp = Cursor::new(Vec::new());
+        let mut shx = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::with_shx(&mut shp, &mut shx)
+            .write_shapes(&points)
+            .unwrap();
+        let (shp, shx) = (shp.into_inner(), shx.into_inner());
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp), ("a.shx", &shx)])).unwrap();
+        assert!(matches!(zipped.shape_at(2).unwrap(), Some(Shape::Point(p)) if p == points[2]));
+        assert!(matches!(zipped.shape_at(0).unwrap(), Some(Shape::Point(p)) if p == points[0]));
+        assert!(zipped.shape_at(3).unwrap().is_none());
+
+        // the missing `.shx` gets rebuilt
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        assert!(matches!(zipped.shape_at(1).unwrap(), Some(Shape::Point(p)) if p == points[1]));
+        assert!(zipped.shape_at(3).unwrap().is_none());
+        #[cfg(feature = "streaming")]
+        {
+            let streaming = zipped.shape_reader_streaming().unwrap().read_nth_shape(2);
+            assert!(matches!(streaming, Some(Ok(Shape::Point(p))) if p == points[2]));
+        }
+    }
+
+    #[test]
+    fn password() {
+        use zip::unstable::write::FileOptionsExt;
+
+        let shp = points_shp(&[shapefile::Point::new(1.0, 2.0)]);
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().with_deprecated_encryption(b"hunter2");
+        writer.start_file("a.shp", options).unwrap();
+        writer.write_all(&shp).unwrap();
+        let mut archive = writer.finish().unwrap();
+        archive.set_position(0);
+
+        let mut zipped = ZippedShapefile::new(archive.clone()).unwrap();
+        assert_eq!(zipped.uncompressed_size(), shp.len() as u64);
+        assert!(matches!(
+            zipped.shape_reader(),
+            Err(Error::PasswordRequired(name)) if name == "a.shp"
+        ));
+
+        let mut zipped = ZippedShapefile::new_with_password(archive.clone(), "hunter2").unwrap();
+        let shapes = zipped.shape_reader().unwrap().read().unwrap();
+        assert!(matches!(shapes[..], [Shape::Point(p)] if p == shapefile::Point::new(1.0, 2.0)));
+
+        // the password check has a 1 in 256 chance of passing anyway
+        let mut zipped = ZippedShapefile::new_with_password(archive, "hunter3").unwrap();
+        assert!(matches!(
+            zipped.shape_reader(),
+            Err(Error::InvalidPassword(name)) if name == "a.shp"
+        ));
+
+        // unencrypted members are read as usual
+        let shapes = ZippedShapefile::new_with_password(zip_of(&[("a.shp", &shp)]), "hunter2")
+            .unwrap()
+            .shape_reader()
+            .unwrap()
+            .read()
+            .unwrap();
+        assert_eq!(shapes.len(), 1);
+    }
+
+    #[test]
+    fn members() {
+        let shp = shp_of(&[0]);
+        let members = [
+            ("a.shp", &shp[..]),
+            ("a.sbn", b""),
+            ("a.shp.xml", b""),
+            ("b.shp", &shp),
+            ("README.txt", b""),
+        ];
+        let zipped = ZippedShapefile::open_layer(zip_of(&members), "a").unwrap();
+        let mut names = zipped.members();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["README.txt", "a.sbn", "a.shp", "a.shp.xml", "b.shp"]
+        );
+        let mut sidecars = zipped.sidecar_members();
+        sidecars.sort();
+        assert_eq!(sidecars, vec!["README.txt", "a.sbn", "a.shp.xml"]);
+    }
+
+    #[test]
+    fn from_bytes() {
+        let archive = zip_of(&[("a.shp", &shp_of(&[0]))]).into_inner();
+        let mut zipped = ZippedShapefile::from_slice(&archive).unwrap();
+        assert_eq!(zipped.len().unwrap(), 1);
+        let mut zipped = ZippedShapefile::from_reader(&archive[..]).unwrap();
+        assert_eq!(zipped.len().unwrap(), 1);
+        let mut zipped = ZippedShapefile::from_bytes(archive).unwrap();
+        assert_eq!(zipped.len().unwrap(), 1);
+    }
+
+    #[test]
+    fn archive_comment() {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.set_comment(r#"{"source": "survey"}"#);
+        writer.start_file("a.shp", FileOptions::default()).unwrap();
+        writer.write_all(&shp_of(&[0])).unwrap();
+        let mut archive = writer.finish().unwrap();
+        archive.set_position(0);
+        let zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(zipped.archive_comment(), br#"{"source": "survey"}"#);
+
+        let zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        assert_eq!(zipped.archive_comment(), b"");
+    }
+
+    #[test]
+    fn uppercase_extensions() {
+        let shp = shp_of(&[0]);
+        let dbf = names_dbf(&["x"], dbase::UnicodeLossy);
+        let archive = zip_of(&[("PARCELS.SHP", &shp), ("PARCELS.DBF", &dbf)]);
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(zipped.reader().unwrap().read().unwrap().len(), 1);
+
+        let archive = zip_of(&[("a.shp", &shp), ("A.SHP", &shp)]);
+        assert!(matches!(
+            ZippedShapefile::new(archive),
+            Err(Error::MultipleFilesFound(".shp"))
+        ));
+    }
+
+    #[test]
+    fn multiple_layers() {
+        let roads = names_dbf(&["Main St"], dbase::UnicodeLossy);
+        let rivers = names_dbf(&["Charles", "Mystic"], dbase::UnicodeLossy);
+        let archive = zip_of(&[
+            ("roads.shp", &shp_of(&[0])),
+            ("roads.dbf", &roads),
+            ("rivers.shp", &shp_of(&[0, 0])),
+            ("rivers.dbf", &rivers),
+        ]);
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(zipped.layers(), vec!["rivers", "roads"]);
+
+        let mut roads = zipped.layer("roads").unwrap();
+        let (_, mut record) = roads.iter_shapes_and_records().next().unwrap().unwrap();
+        assert_eq!(
+            record.remove("NAME"),
+            Some(FieldValue::Character(Some("Main St".to_owned())))
+        );
+        assert_eq!(zipped.layer("rivers").unwrap().read().unwrap().len(), 2);
+
+        assert!(matches!(
+            zipped.layer("canals"),
+            Err(Error::LayerNotFound(stem)) if stem == "canals"
+        ));
+        assert!(matches!(
+            zipped.reader(),
+            Err(Error::MultipleFilesFound(".shp"))
+        ));
+    }
+
+    #[test]
+    fn layer_projections() {
+        let members = [
+            ("roads.shp", &shp_of(&[0])[..]),
+            ("roads.prj", b"GEOGCS[\"a\"]"),
+            ("rivers.shp", &shp_of(&[0])),
+            ("RIVERS.PRJ", b"GEOGCS[\"b\"]"),
+            ("canals.shp", &shp_of(&[0])),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.projection().unwrap(), None);
+        assert_eq!(
+            zipped.layer_projection("roads").unwrap(),
+            Some("GEOGCS[\"a\"]")
+        );
+        assert_eq!(
+            zipped.layer_projection_bytes("rivers").unwrap(),
+            Some(&b"GEOGCS[\"b\"]"[..])
+        );
+        assert_eq!(zipped.layer_projection("canals").unwrap(), None);
+        assert!(matches!(
+            zipped.layer_projection("lakes"),
+            Err(Error::LayerNotFound(_))
+        ));
+
+        // a .prj belonging to no layer goes for all of them
+        let members = [
+            ("roads.shp", &shp_of(&[0])[..]),
+            ("rivers.shp", &shp_of(&[0])),
+            ("project.prj", b"GEOGCS[\"a\"]"),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.projection().unwrap(), Some("GEOGCS[\"a\"]"));
+        assert_eq!(
+            zipped.layer_projection("rivers").unwrap(),
+            Some("GEOGCS[\"a\"]")
+        );
+
+        // a single layer still gets only one
+        let members = [
+            ("roads.shp", &shp_of(&[0])[..]),
+            ("roads.prj", b"GEOGCS[\"a\"]"),
+            ("ROADS.PRJ", b"GEOGCS[\"b\"]"),
+        ];
+        assert!(matches!(
+            ZippedShapefile::new(zip_of(&members)),
+            Err(Error::MultipleFilesFound(".prj"))
+        ));
+        let members = [
+            ("roads.shp", &shp_of(&[0])[..]),
+            ("roads.prj", b"GEOGCS[\"a\"]"),
+            ("rivers.prj", b"GEOGCS[\"b\"]"),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.projection().unwrap(), Some("GEOGCS[\"a\"]"));
+    }
+
+    #[test]
+    fn vertex_counts() {
+        let line = shapefile::Polyline::with_parts(vec![
+            vec![
+                shapefile::Point::new(0.0, 0.0),
+                shapefile::Point::new(1.0, 1.0),
+            ],
+            vec![
+                shapefile::Point::new(2.0, 2.0),
+                shapefile::Point::new(3.0, 3.0),
+                shapefile::Point::new(4.0, 4.0),
+            ],
+        ]);
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[line])
+            .unwrap();
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", shp.get_ref())])).unwrap();
+        assert_eq!(zipped.vertex_counts().unwrap(), vec![5]);
+
+        let points = [
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &points_shp(&points))])).unwrap();
+        assert_eq!(zipped.vertex_counts().unwrap(), vec![1, 1]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        assert_eq!(zipped.vertex_counts().unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn part_counts() {
+        let line = shapefile::Polyline::with_parts(vec![
+            vec![
+                shapefile::Point::new(0.0, 0.0),
+                shapefile::Point::new(1.0, 1.0),
+            ],
+            vec![
+                shapefile::Point::new(2.0, 2.0),
+                shapefile::Point::new(3.0, 3.0),
+            ],
+        ]);
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[line])
+            .unwrap();
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", shp.get_ref())])).unwrap();
+        assert_eq!(zipped.part_counts().unwrap(), vec![2]);
+
+        let points = [shapefile::Point::new(1.0, 2.0)];
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &points_shp(&points))])).unwrap();
+        assert_eq!(zipped.part_counts().unwrap(), vec![1]);
+        let multipoint = shapefile::Multipoint::new(vec![
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+            shapefile::Point::new(5.0, 6.0),
+        ]);
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[multipoint])
+            .unwrap();
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", shp.get_ref())])).unwrap();
+        assert_eq!(zipped.part_counts().unwrap(), vec![3]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        assert_eq!(zipped.part_counts().unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn feature_bboxes() {
+        let points = [
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &points_shp(&points))])).unwrap();
+        assert_eq!(
+            zipped.feature_bboxes().unwrap(),
+            vec![[1.0, 2.0, 1.0, 2.0], [3.0, 4.0, 3.0, 4.0]]
+        );
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        assert!(zipped.feature_bboxes().unwrap()[0]
+            .iter()
+            .all(|value| value.is_nan()));
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn arrow_batches() {
+        let points = [
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+            shapefile::Point::new(5.0, 6.0),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&[
+            ("a.shp", &points_shp(&points)),
+            ("a.dbf", &names_dbf(&["x", "y", "z"], dbase::UnicodeLossy)),
+        ]))
+        .unwrap();
+        let batch = zipped.to_arrow(true).unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        let names: Vec<_> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect();
+        assert_eq!(names, ["NAME", "geometry"]);
+
+        let sizes = zipped
+            .arrow_batches(2, true)
+            .unwrap()
+            .map(|batch| batch.unwrap().num_rows())
+            .collect::<Vec<_>>();
+        assert_eq!(sizes, [2, 1]);
+
+        // attributes can be had even when the shapes can't be converted
+        let strip = shapefile::Multipatch::new(shapefile::Patch::TriangleStrip(vec![
+            shapefile::PointZ::new(0.0, 0.0, 0.0, 0.0),
+            shapefile::PointZ::new(1.0, 0.0, 0.0, 0.0),
+            shapefile::PointZ::new(0.0, 1.0, 0.0, 0.0),
+        ]));
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[strip])
+            .unwrap();
+        let mut zipped = ZippedShapefile::new(zip_of(&[
+            ("a.shp", shp.get_ref()),
+            ("a.dbf", &names_dbf(&["x"], dbase::UnicodeLossy)),
+        ]))
+        .unwrap();
+        assert!(matches!(
+            zipped.to_arrow(true),
+            Err(Error::UnsupportedShapeType(_))
+        ));
+        let batch = zipped.to_arrow(false).unwrap();
+        assert_eq!((batch.num_rows(), batch.num_columns()), (1, 1));
+        assert_eq!(batch.schema().field(0).name(), "NAME");
+    }
+
+    #[cfg(feature = "polars")]
+    #[test]
+    fn to_polars() {
+        let points = [
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&[
+            ("a.shp", &points_shp(&points)),
+            ("a.dbf", &names_dbf(&["x", "y"], dbase::UnicodeLossy)),
+        ]))
+        .unwrap();
+        let frame = zipped.to_polars(true).unwrap();
+        assert_eq!(frame.shape(), (2, 2));
+        assert_eq!(frame.get_column_names(), ["NAME", "geometry"]);
+        let frame = zipped.to_polars(false).unwrap();
+        assert_eq!(frame.get_column_names(), ["NAME"]);
+    }
+
+    #[test]
+    fn load() {
+        let points = [
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ];
+        let archive = zip_of(&[
+            ("a.shp", &points_shp(&points)),
+            ("a.dbf", &names_dbf(&["x", "y"], dbase::UnicodeLossy)),
+            ("a.prj", b"GEOGCS[\"a\"]"),
+        ]);
+        let layer = ZippedShapefile::new(archive).unwrap().load().unwrap();
+        assert_eq!(layer.len(), 2);
+        assert!(matches!(layer[1].0, Shape::Point(p) if p == points[1]));
+        assert_eq!(
+            layer[1].1.get("NAME"),
+            Some(&FieldValue::Character(Some("y".to_owned())))
+        );
+        assert!(layer.get(2).is_none());
+        assert_eq!(layer.iter().count(), 2);
+        assert_eq!(layer.projection(), Some("GEOGCS[\"a\"]"));
+        assert_eq!(layer.schema().unwrap()[0].name, "NAME");
+    }
+
+    #[cfg(feature = "glob")]
+    #[test]
+    fn layers_matching() {
+        let shp = shp_of(&[0]);
+        let archive = zip_of(&[
+            ("tl_2023_01_roads.shp", &shp),
+            ("TL_2023_02_ROADS.shp", &shp),
+            ("tl_2023_01_rails.shp", &shp),
+            ("old/tl_2022_01_roads.shp", &shp),
+        ]);
+        let zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(
+            zipped.layers_matching("tl_2023_*_roads").unwrap(),
+            vec!["TL_2023_02_ROADS", "tl_2023_01_roads"]
+        );
+        assert_eq!(
+            zipped.layers_matching("*/tl_*").unwrap(),
+            vec!["old/tl_2022_01_roads"]
+        );
+        assert!(zipped.layers_matching("canals").unwrap().is_empty());
+        assert!(matches!(
+            zipped.layers_matching("[roads"),
+            Err(Error::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn backslashes() {
+        let shp = shp_of(&[0]);
+        let dbf = names_dbf(&["x"], dbase::UnicodeLossy);
+        let archive = zip_of(&[
+            ("folder\\thing.shp", &shp),
+            ("folder/thing.dbf", &dbf),
+            ("folder\\other.v2\\thing.shp", &shp),
+        ]);
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(
+            zipped.layers(),
+            vec!["folder/other.v2/thing", "folder/thing"]
+        );
+        // the pairs only come out if the `.dbf` got matched up
+        assert_eq!(
+            zipped.layer("folder\\thing").unwrap().read().unwrap().len(),
+            1
+        );
+        assert_eq!(
+            zipped.layer("folder/thing").unwrap().read().unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    fn schema() {
+        let mut dbf = Cursor::new(Vec::new());
+        dbase::TableWriterBuilder::new()
+            .add_character_field("NAME".try_into().unwrap(), 40)
+            .add_numeric_field("AREA".try_into().unwrap(), 12, 3)
+            .add_date_field("SURVEYED".try_into().unwrap())
+            .build_with_dest(&mut dbf)
+            .write_records(&Vec::<dbase::Record>::new())
+            .unwrap();
+        let members = [("a.shp", &shp_of(&[])[..]), ("a.dbf", dbf.get_ref())];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let field = |name: &str, field_type, length, decimal_count| FieldInfo {
+            name: name.to_owned(),
+            field_type,
+            length,
+            decimal_count,
+        };
+        assert_eq!(
+            zipped.schema().unwrap(),
+            Some(vec![
+                field("NAME", FieldType::Character, 40, 0),
+                field("AREA", FieldType::Numeric, 12, 3),
+                field("SURVEYED", FieldType::Date, 8, 0),
+            ])
+        );
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[]))])).unwrap();
+        assert_eq!(zipped.schema().unwrap(), None);
+        assert_eq!(zipped.types().unwrap(), None);
+    }
+
+    #[test]
+    fn to_csv() {
+        let members = [
+            ("a.shp", &shp_of(&[0, 0])[..]),
+            (
+                "a.dbf",
+                &names_dbf(&["Smith, J.", "plain"], dbase::UnicodeLossy),
+            ),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let mut out = Vec::new();
+        zipped.to_csv(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "NAME\r\n\"Smith, J.\"\r\nplain\r\n"
+        );
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        assert!(matches!(zipped.to_csv(Vec::new()), Err(Error::NoDbfFound)));
+    }
+
+    #[test]
+    fn numeric_range() {
+        let mut dbf = Cursor::new(Vec::new());
+        let mut writer = dbase::TableWriterBuilder::new()
+            .add_character_field("NAME".try_into().unwrap(), 8)
+            .add_numeric_field("AREA".try_into().unwrap(), 10, 2)
+            .build_with_dest(&mut dbf);
+        for area in [None, Some(2.5), None, Some(-1.0), Some(7.25)] {
+            let mut record = dbase::Record::default();
+            record.insert("NAME".to_owned(), FieldValue::Character(None));
+            record.insert("AREA".to_owned(), FieldValue::Numeric(area));
+            writer.write_record(&record).unwrap();
+        }
+        drop(writer);
+        let members = [("a.shp", &shp_of(&[0; 5])[..]), ("a.dbf", dbf.get_ref())];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.numeric_range("AREA").unwrap(), Some((-1.0, 7.25)));
+        assert!(matches!(
+            zipped.numeric_range("NAME"),
+            Err(Error::FieldNotNumeric(_))
+        ));
+        assert!(matches!(
+            zipped.numeric_range("ZONE"),
+            Err(Error::FieldNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn value_counts() {
+        let mut dbf = Cursor::new(Vec::new());
+        let mut writer = dbase::TableWriterBuilder::new()
+            .add_character_field("ZONE".try_into().unwrap(), 8)
+            .add_numeric_field("LANES".try_into().unwrap(), 4, 0)
+            .build_with_dest(&mut dbf);
+        for (zone, lanes) in [
+            (Some("R1"), 2.0),
+            (None, 4.0),
+            (Some("C2"), 2.0),
+            (Some("R1"), 2.0),
+        ] {
+            let mut record = dbase::Record::default();
+            record.insert(
+                "ZONE".to_owned(),
+                FieldValue::Character(zone.map(str::to_owned)),
+            );
+            record.insert("LANES".to_owned(), FieldValue::Numeric(Some(lanes)));
+            writer.write_record(&record).unwrap();
+        }
+        drop(writer);
+        let members = [("a.shp", &shp_of(&[0; 4])[..]), ("a.dbf", dbf.get_ref())];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let counts = |pairs: &[(&str, usize)]| {
+            pairs
+                .iter()
+                .map(|&(value, count)| (value.to_owned(), count))
+                .collect::<HashMap<_, _>>()
+        };
+        assert_eq!(
+            zipped.value_counts("ZONE").unwrap(),
+            counts(&[("R1", 2), ("C2", 1), ("", 1)])
+        );
+        assert_eq!(
+            zipped.value_counts("LANES").unwrap(),
+            counts(&[("2", 3), ("4", 1)])
+        );
+        assert!(matches!(
+            zipped.value_counts("NAME"),
+            Err(Error::FieldNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn directory() {
+        let shp = shp_of(&[0, 0]);
+        let dbf = names_dbf(&["a", "b"], dbase::UnicodeLossy);
+        let prj = br#"GEOGCS["WGS 84",AUTHORITY["EPSG","4326"]]"#;
+        let dir = dir_of(
+            "directory",
+            &[
+                ("a.shp", &shp),
+                ("a.DBF", &dbf),
+                ("a.prj", prj),
+                ("notes.txt", b""),
+            ],
+        );
+        std::fs::create_dir(dir.join("b.shp")).unwrap();
+
+        let mut unzipped = DirShapefile::open(&dir).unwrap();
+        assert_eq!(unzipped.layers(), vec!["a"]);
+        assert_eq!(unzipped.projection_epsg().unwrap(), Some(4326));
+        assert_eq!(unzipped.reader().unwrap().read().unwrap().len(), 2);
+        assert_eq!(
+            unzipped.types().unwrap(),
+            Some(vec![("NAME".to_owned(), "dbase::Character".to_owned())])
+        );
+
+        let empty = dir_of("directory-empty", &[("a.dbf", &dbf)]);
+        assert!(matches!(DirShapefile::open(&empty), Err(Error::NoShpFound)));
+
+        std::fs::remove_dir_all(dir).unwrap();
+        std::fs::remove_dir_all(empty).unwrap();
+    }
+
+    #[test]
+    fn open_layer() {
+        let dbf = names_dbf(&["x"], dbase::UnicodeLossy);
+        let members = [
+            ("layer.shp", &shp_of(&[0])[..]),
+            ("layer.dbf", &dbf),
+            ("layer.prj", b"GEOGCS[\"a\"]"),
+            ("layer_backup.shp", &shp_of(&[0, 0])),
+            ("layer_backup.prj", b"GEOGCS[\"b\"]"),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.projection().unwrap(), None);
+
+        let mut zipped = ZippedShapefile::open_layer(zip_of(&members), "layer").unwrap();
+        assert_eq!(zipped.layers(), vec!["layer"]);
+        assert_eq!(zipped.projection().unwrap(), Some("GEOGCS[\"a\"]"));
+        assert_eq!(zipped.reader().unwrap().read().unwrap().len(), 1);
+
+        assert!(matches!(
+            ZippedShapefile::open_layer(zip_of(&members), "other"),
+            Err(Error::LayerNotFound(stem)) if stem == "other"
+        ));
+    }
+
+    #[test]
+    fn into_inner() {
+        let archive = zip_of(&[("a.shp", &shp_of(&[0]))]);
+        let bytes = archive.get_ref().clone();
+        let zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(zipped.into_inner().into_inner(), bytes);
+    }
+
+    #[test]
+    fn nested_layers() {
+        let shp = shp_of(&[0]);
+        let dbf = names_dbf(&["x"], dbase::UnicodeLossy);
+        let archive = zip_of(&[("data/parcels.shp", &shp), ("data/parcels.dbf", &dbf)]);
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(zipped.layers(), vec!["data/parcels"]);
+        assert_eq!(zipped.reader().unwrap().read().unwrap().len(), 1);
+
+        let archive = zip_of(&[
+            ("a/parcels.shp", &shp),
+            ("a/parcels.dbf", &dbf),
+            ("b.v2/parcels.shp", &shp_of(&[0, 0])),
+            (
+                "b.v2/parcels.dbf",
+                &names_dbf(&["y", "z"], dbase::UnicodeLossy),
+            ),
+        ]);
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(zipped.layers(), vec!["a/parcels", "b.v2/parcels"]);
+        assert_eq!(zipped.layer("a/parcels").unwrap().read().unwrap().len(), 1);
+        assert_eq!(
+            zipped.layer("b.v2/parcels").unwrap().read().unwrap().len(),
+            2
+        );
+        assert!(matches!(
+            zipped.layer("parcels"),
+            Err(Error::LayerNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn shared_buffers() {
+        let archive = zip_of(&[("a.shp", &shp_of(&[0]))]);
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        let first = zipped.read_member("a.shp").unwrap();
+        let second = zipped.read_member("a.shp").unwrap();
+        assert!(Arc::ptr_eq(first.get_ref(), second.get_ref()));
+        assert_eq!(zipped.shape_reader().unwrap().read().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn cache_capacity() {
+        let shp = shp_of(&[0; 10]);
+        let dbf = names_dbf(&["a"; 10], dbase::UnicodeLossy);
+        let shx = shx::build(&mut Cursor::new(&shp)).unwrap();
+        let members = [("a.shp", &shp[..]), ("a.shx", &shx), ("a.dbf", &dbf)];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.cache_capacity_bytes(), None);
+        zipped.reader().unwrap();
+        zipped.reader().unwrap();
+        let total = (shp.len() + shx.len() + dbf.len()) as u64;
+        assert_eq!(zipped.cache_stats(), (3, 3, total));
+
+        // the .dbf was used longest ago
+        let capacity = shp.len() + shx.len();
+        zipped.set_cache_capacity_bytes(capacity);
+        assert_eq!(zipped.cache_capacity_bytes(), Some(capacity));
+        assert_eq!(zipped.cache_stats(), (3, 3, capacity as u64));
+        zipped.shape_reader().unwrap();
+        assert_eq!(zipped.cache_stats(), (5, 3, capacity as u64));
+        zipped.dbf_reader().unwrap();
+        let (hits, misses, bytes) = zipped.cache_stats();
+        assert_eq!((hits, misses), (5, 4));
+        assert!(bytes <= capacity as u64);
+
+        zipped.clear_cache();
+        assert_eq!(zipped.cache_stats().2, 0);
+    }
+
+    #[test]
+    fn cache() {
+        let dbf = names_dbf(&["a"], dbase::UnicodeLossy);
+        let dir = dir_of("cache", &[("a.shp", &shp_of(&[0])), ("a.dbf", &dbf)]);
+        let mut unzipped = DirShapefile::open(&dir).unwrap();
+        assert_eq!(unzipped.reader().unwrap().read().unwrap().len(), 1);
+
+        std::fs::remove_file(dir.join("a.dbf")).unwrap();
+        assert!(unzipped.dbf_reader().unwrap().is_some());
+        assert_eq!(unzipped.reader().unwrap().read().unwrap().len(), 1);
+
+        unzipped.clear_cache();
+        assert!(matches!(
+            unzipped.dbf_reader(),
+            Err(Error::MemberRead { name, source })
+                if name == "a.dbf" && matches!(*source, Error::IOError(_))
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn memo() {
+        // a dBASE III table with memos, whose `NOTE` field points at block 1
+        let mut dbf = names_dbf(&["1"], dbase::UnicodeLossy);
+        dbf[0] = 0x83;
+        dbf[32 + 11] = b'M';
+        dbf[32] = b'N';
+        dbf[33..36].copy_from_slice(b"OTE");
+        let mut dbt = vec![0; 512];
+        dbt[..4].copy_from_slice(&2u32.to_le_bytes());
+        dbt.extend_from_slice(b"a rather long note\x1a\x1a");
+        dbt.resize(1024, 0);
+
+        let shp = shp_of(&[0]);
+        let members = [("a.shp", &shp[..]), ("a.dbf", &dbf), ("a.DBT", &dbt)];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let mut records = zipped.dbf_reader().unwrap().unwrap().read().unwrap();
+        assert_eq!(
+            records[0].remove("NOTE"),
+            Some(FieldValue::Memo("a rather long note".to_owned()))
+        );
+
+        let members = [("a.shp", &shp[..]), ("a.dbt", &dbt), ("a.fpt", &dbt)];
+        assert!(matches!(
+            ZippedShapefile::new(zip_of(&members)),
+            Err(Error::MultipleFilesFound(MEMO))
+        ));
+    }
+
+    #[test]
+    fn projection_epsg() {
+        let shp = shp_of(&[]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        assert_eq!(zipped.projection_epsg().unwrap(), None);
+
+        let prj = br#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563]],PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433],AUTHORITY["EPSG","4326"]]"#;
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp), ("a.prj", prj)])).unwrap();
+        assert_eq!(zipped.projection_epsg().unwrap(), Some(4326));
+        assert_eq!(
+            zipped.crs().unwrap().unwrap().name.as_deref(),
+            Some("WGS 84")
+        );
+    }
+
+    #[test]
+    fn non_utf8_projection() {
+        let prj = b"GEOGCS[\"WGS 84 \xff\",AUTHORITY[\"EPSG\",\"4326\"]]";
+        let archive = zip_of(&[("a.shp", &shp_of(&[0])), ("a.prj", prj)]);
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(zipped.projection_bytes().unwrap(), Some(&prj[..]));
+        assert_eq!(
+            zipped.projection().unwrap(),
+            Some("GEOGCS[\"WGS 84 \u{fffd}\",AUTHORITY[\"EPSG\",\"4326\"]]")
+        );
+        assert_eq!(zipped.projection_epsg().unwrap(), Some(4326));
+        assert_eq!(zipped.shape_reader().unwrap().read().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn projection_bom() {
+        let wkt = r#"GEOGCS["WGS 84",AUTHORITY["EPSG","4326"]]"#;
+        let prj = [&b"\xef\xbb\xbf"[..], wkt.as_bytes()].concat();
+        let cpg = b"\xef\xbb\xbfUTF-8";
+        let archive = zip_of(&[("a.shp", &shp_of(&[0])), ("a.prj", &prj), ("a.cpg", cpg)]);
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(zipped.projection().unwrap(), Some(wkt));
+        assert_eq!(zipped.projection_bytes().unwrap(), Some(&prj[..]));
+        assert_eq!(zipped.projection_epsg().unwrap(), Some(4326));
+        assert_eq!(zipped.encoding(), Some("UTF-8"));
+    }
+
+    #[test]
+    fn require_components() {
+        let shp = shp_of(&[0]);
+        let dbf = names_dbf(&["x"], dbase::UnicodeLossy);
+        let prj = b"GEOGCS[\"a\"]";
+        let build = |members: &[(&str, &[u8])], components| {
+            ZippedShapefileBuilder::new()
+                .require_components(components)
+                .build(zip_of(members))
+        };
+        let bare = [("a.shp", &shp[..])];
+        assert!(build(&bare, Components::NONE).is_ok());
+        assert!(matches!(
+            build(&bare, Components::SHX),
+            Err(Error::MissingShx(layer)) if layer == "a"
+        ));
+        assert!(matches!(
+            build(&bare, Components::DBF),
+            Err(Error::MissingDbf(layer)) if layer == "a"
+        ));
+        let half = [("a.shp", &shp[..]), ("a.dbf", &dbf), ("b.shp", &shp)];
+        assert!(matches!(
+            build(&half, Components::DBF),
+            Err(Error::MissingDbf(layer)) if layer == "b"
+        ));
+        assert!(matches!(
+            build(&bare, Components::SHX | Components::PRJ),
+            Err(Error::MissingShx(_))
+        ));
+        assert!(matches!(
+            build(&[("a.shp", &shp), ("a.shx", &shx::build(&mut Cursor::new(&shp)).unwrap())], Components::SHX | Components::PRJ),
+            Err(Error::MissingProjection(layer)) if layer == "a"
+        ));
+        assert!(build(&[("a.shp", &shp), ("a.prj", prj)], Components::PRJ).is_ok());
+
+        // a lone .prj for several layers covers them all, but not once a
+        // layer has its own
+        let shared = [("a.shp", &shp[..]), ("b.shp", &shp), ("c.prj", prj)];
+        assert!(build(&shared, Components::PRJ).is_ok());
+        let own = [("a.shp", &shp[..]), ("b.shp", &shp), ("a.prj", prj)];
+        assert!(matches!(
+            build(&own, Components::PRJ),
+            Err(Error::MissingProjection(layer)) if layer == "b"
+        ));
+    }
+
+    #[test]
+    fn strict() {
+        let shp = shp_of(&[0]);
+        let dbf = names_dbf(&["x"], dbase::UnicodeLossy);
+        let build = |members: &[(&str, &[u8])]| {
+            ZippedShapefileBuilder::new()
+                .strict()
+                .build(zip_of(members))
+        };
+        assert!(build(&[("a.shp", &shp), ("A.DBF", &dbf), ("a.cpg", b"UTF-8")]).is_ok());
+        assert!(matches!(
+            build(&[("a.shp", &shp), ("b.dbf", &dbf)]),
+            Err(Error::UnmatchedComponent(member)) if member == "b.dbf"
+        ));
+        assert!(matches!(
+            build(&[("a.shp", &shp), ("b.shp", &shp), ("c.prj", b"")]),
+            Err(Error::UnmatchedComponent(member)) if member == "c.prj"
+        ));
+        // sidecars aren't components, whatever they're called
+        assert!(build(&[("a.shp", &shp), ("b.sbn", b"")]).is_ok());
+    }
+
+    #[test]
+    fn mismatched_stems() {
+        let members = [
+            ("roads.shp", &shp_of(&[0])[..]),
+            ("roads_table.dbf", &names_dbf(&["x"], dbase::UnicodeLossy)),
+            ("roads_table.prj", b"GEOGCS[\"a\"]"),
+            ("other.cpg", b"UTF-8"),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let components = zipped.components().unwrap();
+        assert!(!components.has_dbf && !components.has_prj && !components.has_cpg);
+        assert_eq!(zipped.projection().unwrap(), None);
+        assert_eq!(zipped.encoding(), None);
+    }
+
+    #[test]
+    fn builder() {
+        use dbase::{encoding::LossyCodePage, yore::code_pages::CP1252};
+
+        let shp = shp_of(&[0]);
+        let mut dbf = names_dbf(&["Montréal"], LossyCodePage(CP1252));
+        dbf[29] = 0;
+        let prj = b"GEOGCS[\"W\xe9\"]";
+        let members = [("a.shp", &shp[..]), ("a.dbf", &dbf), ("a.prj", prj)];
+
+        let mut zipped = ZippedShapefileBuilder::new()
+            .build(zip_of(&members))
+            .unwrap();
+        assert_eq!(zipped.encoding(), None);
+        assert_eq!(zipped.projection().unwrap(), Some("GEOGCS[\"W\u{FFFD}\"]"));
+        assert_eq!(zipped.max_member_size(), None);
+        assert_eq!(names(&mut zipped).len(), 1);
+
+        let mut zipped = ZippedShapefileBuilder::new()
+            .encoding_override("CP1252")
+            .max_member_size(prj.len() as u64)
+            .build(zip_of(&members))
+            .unwrap();
+        assert_eq!(zipped.encoding(), Some("CP1252"));
+        assert_eq!(zipped.max_member_size(), Some(prj.len() as u64));
+        assert!(matches!(zipped.reader(), Err(Error::MemberSizeTooLarge(_))));
+        zipped.set_max_member_size(u64::MAX);
+        assert_eq!(
+            names(&mut zipped),
+            vec![FieldValue::Character(Some("Montréal".to_owned()))]
+        );
+
+        // the .prj is only read when asked for
+        let mut zipped = ZippedShapefileBuilder::new()
+            .max_member_size(prj.len() as u64 - 1)
+            .build(zip_of(&members))
+            .unwrap();
+        assert!(matches!(
+            zipped.projection(),
+            Err(Error::MemberSizeTooLarge(_))
+        ));
+        let mut zipped = ZippedShapefileBuilder::new()
+            .prj_lossy(false)
+            .build(zip_of(&members))
+            .unwrap();
+        assert!(matches!(zipped.projection(), Err(Error::NonUtf8Projection)));
+
+        let archive = zip_of(&members).into_inner();
+        let zipped = ZippedShapefileBuilder::new()
+            .max_member_size(prj.len() as u64)
+            .from_reader(&archive[..])
+            .unwrap();
+        assert_eq!(zipped.max_member_size(), Some(prj.len() as u64));
+
+        let zipped = ZippedShapefileBuilder::new()
+            .password("hunter2")
+            .build(zip_of(&members[..2]))
+            .unwrap();
+        assert_eq!(zipped.layers(), vec!["a"]);
+    }
+
+    #[test]
+    fn cpg_encoding() {
+        use dbase::{encoding::LossyCodePage, yore::code_pages::CP1252};
+
+        let shp = shp_of(&[0]);
+        let dbf = names_dbf(&["Montréal"], LossyCodePage(CP1252));
+        let montreal = FieldValue::Character(Some("Montréal".to_owned()));
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp), ("a.dbf", &dbf)])).unwrap();
+        assert_eq!(zipped.encoding(), Some("CP1252"));
+        assert_eq!(names(&mut zipped), vec![montreal.clone()]);
+
+        let mut unmarked = dbf.clone();
+        unmarked[29] = 0;
+        let mut zipped =
+            ZippedShapefile::new(zip_of(&[("a.shp", &shp), ("a.dbf", &unmarked)])).unwrap();
+        assert_eq!(zipped.encoding(), None);
+        assert_ne!(names(&mut zipped), vec![montreal.clone()]);
+
+        let members = [
+            ("a.shp", &shp[..]),
+            ("a.dbf", &dbf),
+            ("a.cpg", b"ISO-8859-1\r\n"),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.encoding(), Some("ISO-8859-1"));
+        assert_eq!(names(&mut zipped), vec![montreal.clone()]);
+        let mut reader = zipped.reader().unwrap();
+        let (_, mut record) = reader.iter_shapes_and_records().next().unwrap().unwrap();
+        assert_eq!(record.remove("NAME"), Some(montreal));
+    }
+
+    #[test]
+    fn layer_encodings() {
+        use dbase::{encoding::LossyCodePage, yore::code_pages::CP1252};
+
+        let shp = shp_of(&[0]);
+        let utf8 = names_dbf(&["Montréal"], dbase::UnicodeLossy);
+        let cp1252 = names_dbf(&["Montréal"], LossyCodePage(CP1252));
+        let montreal = FieldValue::Character(Some("Montréal".to_owned()));
+        let name = |zipped: &mut ZippedShapefile<_>, stem| {
+            let mut reader = zipped.layer(stem).unwrap();
+            let (_, mut record) = reader.iter_shapes_and_records().next().unwrap().unwrap();
+            record.remove("NAME")
+        };
+
+        let members = [
+            ("roads.shp", &shp[..]),
+            ("roads.dbf", &utf8),
+            ("roads.cpg", b"UTF-8"),
+            ("rivers.shp", &shp),
+            ("rivers.dbf", &cp1252),
+            ("rivers.cpg", b"1252"),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.encoding(), None);
+        assert_eq!(zipped.layer_encoding("roads").unwrap(), Some("UTF-8"));
+        assert_eq!(zipped.layer_encoding("rivers").unwrap(), Some("1252"));
+        assert_eq!(name(&mut zipped, "roads"), Some(montreal.clone()));
+        assert_eq!(name(&mut zipped, "rivers"), Some(montreal.clone()));
+
+        // a .cpg belonging to no layer goes for all of them
+        let members = [
+            ("roads.shp", &shp[..]),
+            ("roads.dbf", &cp1252),
+            ("rivers.shp", &shp),
+            ("rivers.dbf", &cp1252),
+            ("project.cpg", b"1252"),
+        ];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        assert_eq!(zipped.encoding(), Some("1252"));
+        assert_eq!(zipped.layer_encoding("rivers").unwrap(), Some("1252"));
+        assert_eq!(name(&mut zipped, "rivers"), Some(montreal));
+        assert!(matches!(
+            zipped.layer_encoding("lakes"),
+            Err(Error::LayerNotFound(_))
+        ));
+    }
+
+    #[cfg(feature = "geo-types")]
+    #[test]
+    fn geometries() {
+        let shp = points_shp(&[shapefile::Point::new(1.0, 2.0)]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        let geometries = zipped
+            .geometries()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            geometries,
+            vec![geo_types::Geometry::Point(geo_types::Point::new(1.0, 2.0))]
+        );
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn projection_proj4() {
+        let shp = shp_of(&[0]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        assert_eq!(zipped.projection_proj4().unwrap(), None);
+
+        let prj = br#"GEOGCS["WGS 84",AUTHORITY["EPSG","4326"]]"#;
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp), ("a.prj", prj)])).unwrap();
+        let proj4 = zipped.projection_proj4().unwrap().unwrap();
+        assert!(proj4.starts_with("+proj=longlat"), "{}", proj4);
+        assert!(proj4.split(' ').all(|param| param.starts_with('+')));
+
+        let prj = b"not a coordinate system";
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp), ("a.prj", prj)])).unwrap();
+        assert!(matches!(
+            zipped.projection_proj4(),
+            Err(Error::ProjCreate(_))
+        ));
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn reproject_to() {
+        let shp = points_shp(&[shapefile::Point::new(180.0, 0.0)]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        let out = Cursor::new(Vec::new());
+        assert!(matches!(
+            zipped.reproject_to(out, 3857),
+            Err(Error::UnknownCrs)
+        ));
+
+        let prj = br#"GEOGCS["WGS 84",AUTHORITY["EPSG","4326"]]"#;
+        let dbf = names_dbf(&["a"], dbase::UnicodeLossy);
+        let members = [("a.shp", &shp[..]), ("a.dbf", &dbf), ("a.prj", prj)];
+        let mut zipped = ZippedShapefile::new(zip_of(&members)).unwrap();
+        let mut out = Cursor::new(Vec::new());
+        zipped.reproject_to(&mut out, 3857).unwrap();
+
+        let mut reprojected = ZippedShapefile::from_bytes(out.into_inner()).unwrap();
+        assert!(reprojected
+            .projection()
+            .unwrap()
+            .unwrap()
+            .contains("Pseudo_Mercator"));
+        assert_eq!(names(&mut reprojected), names(&mut zipped));
+        match &reprojected.shape_reader().unwrap().read().unwrap()[..] {
+            [Shape::Point(point)] => {
+                assert!((point.x - 20037508.342789244).abs() < 1e-3);
+                assert!(point.y.abs() < 1e-6);
+            }
+            _ => panic!("expected a single point"),
+        }
+    }
+
+    #[cfg(feature = "proj")]
+    #[test]
+    fn geometries_wgs84() {
+        let shp = points_shp(&[shapefile::Point::new(20037508.342789244, 0.0)]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        assert!(matches!(zipped.geometries_wgs84(), Err(Error::UnknownCrs)));
+
+        let prj = br#"PROJCS["WGS 84 / Pseudo-Mercator",GEOGCS["WGS 84",AUTHORITY["EPSG","4326"]],AUTHORITY["EPSG","3857"]]"#;
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp), ("a.prj", prj)])).unwrap();
+        let geometries = zipped
+            .geometries_wgs84()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        match geometries[..] {
+            [geo_types::Geometry::Point(point)] => {
+                assert!((point.x() - 180.0).abs() < 1e-6);
+                assert!(point.y().abs() < 1e-6);
+            }
+            _ => panic!("expected a single point, got {:?}", geometries),
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn open_all() {
+        let archive = zip_of(&[("a.shp", &shp_of(&[0]))]).into_inner();
+        let dir = dir_of("open_all", &[("a.zip", &archive), ("b.zip", b"not a zip")]);
+        let opened = ZippedShapefile::open_all(
+            ["a.zip", "b.zip", "c.zip", "a.zip"].map(|name| dir.join(name)),
+        );
+        assert!(matches!(
+            opened[..],
+            [
+                Ok(_),
+                Err(Error::CorruptArchive(_)),
+                Err(Error::IOError(_)),
+                Ok(_)
+            ]
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_geometries() {
+        let points: Vec<_> = (0..100)
+            .map(|i| shapefile::Point::new(i as f64, 0.0))
+            .collect();
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &points_shp(&points))])).unwrap();
+        let sequential = zipped
+            .geometries()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        let parallel = zipped
+            .par_geometries()
+            .unwrap()
+            .into_iter()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(parallel, sequential);
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        assert!(matches!(
+            zipped.par_geometries().unwrap()[..],
+            [Err(Error::GeometryConversion(_))]
+        ));
+    }
+
+    #[cfg(feature = "wkt")]
+    #[test]
+    fn wkt_strings() {
+        let shp = points_shp(&[shapefile::Point::new(1.0, 2.0)]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        let wkt = zipped
+            .wkt_strings()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(wkt, vec!["POINT (1 2)"]);
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        let wkt = zipped
+            .wkt_strings()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(wkt, vec!["GEOMETRYCOLLECTION EMPTY"]);
+
+        // a truncated record comes out as an error after the ones before it
+        let shp = points_shp(&[
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp[..shp.len() - 4])])).unwrap();
+        let wkt: Vec<_> = zipped.wkt_strings().unwrap().collect();
+        assert!(matches!(&wkt[..], [Ok(point), Err(_)] if point == "POINT (1 2)"));
+    }
+
+    #[cfg(feature = "geojson")]
+    #[test]
+    fn write_ndgeojson() {
+        let shp = points_shp(&[
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ]);
+        let dbf = names_dbf(&["a", "b"], dbase::UnicodeLossy);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp), ("a.dbf", &dbf)])).unwrap();
+        let mut out = Vec::new();
+        zipped.write_ndgeojson(&mut out).unwrap();
+        let lines: Vec<_> = std::str::from_utf8(&out).unwrap().lines().collect();
+        assert_eq!(lines.len(), 2);
+        let feature: geojson::Feature = lines[1]
+            .parse::<geojson::GeoJson>()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(
+            feature.property("NAME"),
+            Some(&serde_json::Value::from("b"))
+        );
+        assert!(out.ends_with(b"\n"));
+    }
+
+    #[cfg(feature = "geojson")]
+    #[test]
+    fn to_geojson() {
+        let shp = points_shp(&[
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ]);
+        let dbf = names_dbf(&["a", ""], dbase::UnicodeLossy);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp), ("a.dbf", &dbf)])).unwrap();
+        let collection = zipped.to_geojson().unwrap();
+        assert_eq!(collection.features.len(), 2);
+
+        let first = &collection.features[0];
+        assert_eq!(
+            first.geometry,
+            Some(geojson::Geometry::new_point([1.0, 2.0]))
+        );
+        assert_eq!(first.property("NAME"), Some(&geojson::JsonValue::from("a")));
+        let second = &collection.features[1];
+        assert_eq!(second.property("NAME"), Some(&geojson::JsonValue::Null));
+    }
+
+    #[cfg(feature = "wkb")]
+    #[test]
+    fn to_wkb() {
+        let shp = points_shp(&[shapefile::Point::new(1.0, 2.0)]);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp)])).unwrap();
+        let wkb = zipped
+            .to_wkb()
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(wkb.len(), 1);
+        assert_eq!(wkb[0].len(), 21);
+        assert_eq!(wkb[0][..5], [1, 1, 0, 0, 0]);
+    }
+
+    #[cfg(feature = "geo-types")]
+    #[test]
+    fn multipatch() {
+        let strip = shapefile::Multipatch::new(shapefile::Patch::TriangleStrip(vec![
+            shapefile::PointZ::new(0.0, 0.0, 0.0, 0.0),
+            shapefile::PointZ::new(1.0, 0.0, 0.0, 0.0),
+            shapefile::PointZ::new(0.0, 1.0, 0.0, 0.0),
+        ]));
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[strip])
+            .unwrap();
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", shp.get_ref())])).unwrap();
+        assert!(zipped.contains_multipatch().unwrap());
+        assert!(matches!(
+            zipped.geometries().unwrap().next(),
+            Some(Err(Error::UnsupportedShapeType(
+                shapefile::ShapeType::Multipatch
+            )))
+        ));
+
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        assert!(!zipped.contains_multipatch().unwrap());
+    }
+
+    #[cfg(feature = "geo-types")]
+    #[test]
+    fn null_shape_geometry() {
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp_of(&[0]))])).unwrap();
+        let mut geometries = zipped.geometries().unwrap();
+        assert!(matches!(
+            geometries.next(),
+            Some(Err(Error::GeometryConversion(_)))
+        ));
+        assert!(geometries.next().is_none());
+    }
+
+    #[cfg(feature = "geo-types")]
+    #[test]
+    fn truncated_geometries() {
+        let points = [
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ];
+        let shp = points_shp(&points);
+        let mut zipped = ZippedShapefile::new(zip_of(&[("a.shp", &shp[..shp.len() - 4])])).unwrap();
+        let geometries: Vec<_> = zipped.geometries().unwrap().collect();
+        assert!(matches!(
+            geometries[..],
+            [Ok(geo_types::Geometry::Point(_)), Err(_)]
+        ));
     }
 }
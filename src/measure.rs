@@ -0,0 +1,106 @@
+//! Areas, lengths and centroids of geometries, in whatever units their
+//! coordinates are, and simplifying them; all by way of `geo`.
+
+use geo::{Area, Centroid, EuclideanLength, Simplify};
+use geo_types::{Geometry, LineString, Point, Polygon};
+
+/// The area of `geometry`, which is zero for anything but polygons. Ring
+/// orientation doesn't matter, so this is never negative.
+pub(crate) fn area(geometry: &Geometry<f64>) -> f64 {
+    geometry.unsigned_area()
+}
+
+/// The length of `geometry`: the perimeter of polygons, counting their
+/// holes, and zero for points.
+pub(crate) fn length(geometry: &Geometry<f64>) -> f64 {
+    match geometry {
+        Geometry::Point(_) | Geometry::MultiPoint(_) => 0.0,
+        Geometry::Line(line) => line.euclidean_length(),
+        Geometry::LineString(line) => line.euclidean_length(),
+        Geometry::MultiLineString(lines) => lines.euclidean_length(),
+        Geometry::Polygon(polygon) => perimeter(polygon),
+        Geometry::MultiPolygon(polygons) => polygons.iter().map(perimeter).sum(),
+        Geometry::Rect(rect) => perimeter(&rect.to_polygon()),
+        Geometry::Triangle(triangle) => perimeter(&triangle.to_polygon()),
+        Geometry::GeometryCollection(geometries) => geometries.iter().map(length).sum(),
+    }
+}
+
+/// The centroid of `geometry`, or `None` when it's empty.
+pub(crate) fn centroid(geometry: &Geometry<f64>) -> Option<Point<f64>> {
+    geometry.centroid()
+}
+
+/// `geometry` simplified with Douglas–Peucker. Only lines and polygons get
+/// simplified; everything else is left as it is.
+pub(crate) fn simplify(geometry: Geometry<f64>, epsilon: f64) -> Geometry<f64> {
+    match geometry {
+        Geometry::LineString(line) => Geometry::LineString(line.simplify(&epsilon)),
+        Geometry::MultiLineString(lines) => Geometry::MultiLineString(lines.simplify(&epsilon)),
+        Geometry::Polygon(polygon) => Geometry::Polygon(polygon.simplify(&epsilon)),
+        Geometry::MultiPolygon(polygons) => Geometry::MultiPolygon(polygons.simplify(&epsilon)),
+        Geometry::GeometryCollection(geometries) => Geometry::GeometryCollection(
+            geometries
+                .into_iter()
+                .map(|geometry| simplify(geometry, epsilon))
+                .collect(),
+        ),
+        geometry => geometry,
+    }
+}
+
+fn perimeter(polygon: &Polygon<f64>) -> f64 {
+    std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .map(LineString::euclidean_length)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{line_string, point, polygon, MultiLineString};
+
+    #[test]
+    fn polygons() {
+        // clockwise, as shapefiles have their outer rings
+        let holed = Geometry::Polygon(polygon!(
+            exterior: [(x: 0.0, y: 0.0), (x: 0.0, y: 4.0), (x: 4.0, y: 4.0), (x: 4.0, y: 0.0), (x: 0.0, y: 0.0)],
+            interiors: [[(x: 1.0, y: 1.0), (x: 2.0, y: 1.0), (x: 2.0, y: 2.0), (x: 1.0, y: 2.0), (x: 1.0, y: 1.0)]],
+        ));
+        assert_eq!(area(&holed), 15.0);
+        assert_eq!(length(&holed), 20.0);
+        // the hole pulls it away from the middle
+        let centre = centroid(&holed).unwrap();
+        assert!((centre.x() - 30.5 / 15.0).abs() < 1e-9);
+        assert!((centre.y() - 30.5 / 15.0).abs() < 1e-9);
+        assert_eq!(
+            centroid(&Geometry::GeometryCollection(Default::default())),
+            None
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let line = line_string![(x: 0.0, y: 0.0), (x: 3.0, y: 4.0)];
+        let lines = Geometry::MultiLineString(MultiLineString(vec![line.clone(), line]));
+        assert_eq!(area(&lines), 0.0);
+        assert_eq!(length(&lines), 10.0);
+        assert_eq!(length(&Geometry::Point(point!(x: 1.0, y: 1.0))), 0.0);
+    }
+
+    #[test]
+    fn simplified() {
+        let line = line_string![(x: 0.0, y: 0.0), (x: 1.0, y: 0.1), (x: 2.0, y: 0.0)];
+        let lines = Geometry::MultiLineString(MultiLineString(vec![line.clone()]));
+        assert_eq!(
+            simplify(lines.clone(), 0.5),
+            Geometry::MultiLineString(MultiLineString(vec![
+                line_string![(x: 0.0, y: 0.0), (x: 2.0, y: 0.0)]
+            ]))
+        );
+        assert_eq!(simplify(lines.clone(), 0.01), lines);
+        let point = Geometry::Point(point!(x: 1.0, y: 1.0));
+        assert_eq!(simplify(point.clone(), 10.0), point);
+    }
+}
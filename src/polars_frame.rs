@@ -0,0 +1,101 @@
+//! Turning records into a Polars data frame.
+
+use polars::prelude::{
+    Column, DataFrame, DataType, IntoColumn, NamedFrom, PlSmallStr, Series, TimeUnit,
+};
+
+use crate::{
+    columns::{Columns, Values, GEOMETRY},
+    FieldInfo, Result,
+};
+
+/// A series per field in `fields`, which `columns` has to have come from,
+/// then the geometry series if there is one; see `to_polars()` for the
+/// types.
+pub(crate) fn data_frame(fields: &[FieldInfo], columns: Columns) -> Result<DataFrame> {
+    let mut series = Vec::with_capacity(fields.len() + 1);
+    for (field, values) in fields.iter().zip(columns.fields) {
+        series.push(values_series(field, values)?.into_column());
+    }
+    if let Some(geometries) = columns.geometries {
+        series.push(Column::new(GEOMETRY.into(), geometries));
+    }
+    // given the height, so that a frame without any columns still has rows
+    Ok(DataFrame::new_with_height(columns.len, series)?)
+}
+
+fn values_series(field: &FieldInfo, values: Values) -> Result<Series> {
+    let name = PlSmallStr::from(field.name.as_str());
+    Ok(match values {
+        Values::Text(values) => Series::new(name, values),
+        Values::Number(values) => Series::new(name, values),
+        Values::Integer(values) => Series::new(name, values),
+        Values::Logical(values) => Series::new(name, values),
+        // days and milliseconds since the epoch, which is what Polars keeps
+        // underneath
+        Values::Date(values) => Series::new(name, values).cast(&DataType::Date)?,
+        Values::DateTime(values) => {
+            let values: Vec<_> = values
+                .into_iter()
+                .map(|seconds| seconds.map(|seconds| seconds * 1000))
+                .collect();
+            Series::new(name, values).cast(&DataType::Datetime(TimeUnit::Milliseconds, None))?
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FieldType;
+
+    #[test]
+    fn series() {
+        let field = |name: &str, field_type| FieldInfo {
+            name: name.to_owned(),
+            field_type,
+            length: 8,
+            decimal_count: 0,
+        };
+        let fields = [
+            field("ID", FieldType::Integer),
+            field("DAY", FieldType::Date),
+            field("WHEN", FieldType::DateTime),
+        ];
+        let columns = Columns {
+            len: 2,
+            fields: vec![
+                Values::Integer(vec![Some(7), None]),
+                Values::Date(vec![None, Some(1)]),
+                Values::DateTime(vec![Some(2), None]),
+            ],
+            geometries: Some(vec![Some(vec![1, 2]), None]),
+        };
+        let frame = data_frame(&fields, columns).unwrap();
+        assert_eq!(
+            frame.dtypes(),
+            [
+                DataType::Int32,
+                DataType::Date,
+                DataType::Datetime(TimeUnit::Milliseconds, None),
+                DataType::Binary,
+            ]
+        );
+        assert_eq!(frame.get_column_names(), ["ID", "DAY", "WHEN", GEOMETRY]);
+        for column in frame.get_columns() {
+            assert_eq!(column.null_count(), 1);
+        }
+        let when = frame.column("WHEN").unwrap().datetime().unwrap();
+        assert_eq!(when.phys.get(0), Some(2000));
+        let geometries = frame.column(GEOMETRY).unwrap().binary().unwrap();
+        assert_eq!(geometries.get(0), Some(&[1, 2][..]));
+        assert_eq!(geometries.get(1), None);
+
+        let columns = Columns {
+            len: 3,
+            fields: vec![],
+            geometries: None,
+        };
+        assert_eq!(data_frame(&[], columns).unwrap().shape(), (3, 0));
+    }
+}
@@ -0,0 +1,296 @@
+//! A small parser for the WKT found in `.prj` files.
+//!
+//! This only understands the bracketed `KEYWORD[arg, ...]` structure of
+//! WKT (either `[]` or `()` delimiters); it doesn't know anything about the
+//! meaning of individual keywords beyond what the accessors below need.
+
+use std::{iter::Peekable, str::Chars};
+
+/// How deeply nodes can nest before the WKT is given up on. Real ones
+/// don't go past half a dozen levels; this keeps a crafted `.prj` from
+/// overflowing the stack.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(wkt: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = wkt.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '[' | '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ']' | ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Text(quoted(&mut chars)?));
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "[]()\",".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Some(tokens)
+}
+
+/// Reads the rest of a quoted string; WKT escapes `"` by doubling it.
+fn quoted(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut text = String::new();
+    loop {
+        match chars.next()? {
+            '"' if chars.peek() == Some(&'"') => {
+                chars.next();
+                text.push('"');
+            }
+            '"' => return Some(text),
+            c => text.push(c),
+        }
+    }
+}
+
+/// One argument of a WKT node: either a nested node, a quoted string, or a
+/// bare word (numbers and enumerations like `EAST` both end up here).
+#[derive(Debug, PartialEq)]
+pub(crate) enum Value {
+    Node(Node),
+    Text(String),
+    Word(String),
+}
+
+impl Value {
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Text(s) | Value::Word(s) => Some(s),
+            Value::Node(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Node {
+    pub(crate) keyword: String,
+    pub(crate) args: Vec<Value>,
+}
+
+impl Node {
+    /// The direct children of this node that are themselves nodes.
+    pub(crate) fn children(&self) -> impl Iterator<Item = &Node> {
+        self.args.iter().filter_map(|arg| match arg {
+            Value::Node(node) => Some(node),
+            _ => None,
+        })
+    }
+
+    /// The `AUTHORITY[name, code]` attached directly to this node.
+    pub(crate) fn authority(&self) -> Option<(&str, &str)> {
+        let authority = self
+            .children()
+            .find(|child| child.keyword.eq_ignore_ascii_case("AUTHORITY"))?;
+        match &authority.args[..] {
+            [name, code] => Some((name.as_str()?, code.as_str()?)),
+            _ => None,
+        }
+    }
+}
+
+/// Parses a single WKT node; trailing input after it is an error.
+pub(crate) fn parse(wkt: &str) -> Option<Node> {
+    let mut tokens = tokenize(wkt)?.into_iter().peekable();
+    let node = parse_node(&mut tokens)?;
+    match tokens.next() {
+        None => Some(node),
+        Some(_) => None,
+    }
+}
+
+fn parse_node(tokens: &mut Peekable<impl Iterator<Item = Token>>) -> Option<Node> {
+    match tokens.next()? {
+        Token::Word(keyword) => parse_body(keyword, tokens, 1),
+        _ => None,
+    }
+}
+
+/// The rest of the node named `keyword`, itself `depth` levels deep.
+fn parse_body(
+    keyword: String,
+    tokens: &mut Peekable<impl Iterator<Item = Token>>,
+    depth: usize,
+) -> Option<Node> {
+    if depth > MAX_DEPTH || tokens.next()? != Token::Open {
+        return None;
+    }
+    let mut args = Vec::new();
+    loop {
+        let arg = match tokens.next()? {
+            Token::Close if args.is_empty() => break,
+            Token::Text(text) => Value::Text(text),
+            Token::Word(word) if tokens.peek() == Some(&Token::Open) => {
+                Value::Node(parse_body(word, tokens, depth + 1)?)
+            }
+            Token::Word(word) => Value::Word(word),
+            _ => return None,
+        };
+        args.push(arg);
+        match tokens.next()? {
+            Token::Comma => continue,
+            Token::Close => break,
+            _ => return None,
+        }
+    }
+    Some(Node { keyword, args })
+}
+
+/// What a `.prj` says about the coordinate system.
+///
+/// Everything comes from the outermost `PROJCS`/`GEOGCS` node. Authorities on
+/// nested nodes (the datum, the base geographic system of a projection, ...)
+/// are deliberately ignored since they describe something other than the
+/// file's coordinate system.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Crs {
+    /// The authority the code belongs to, usually `EPSG`.
+    pub authority: Option<String>,
+    pub code: Option<u32>,
+    /// The first argument of the node, like `WGS 84 / Pseudo-Mercator`.
+    pub name: Option<String>,
+    /// Whether this is a `GEOGCS` (coordinates are longitude and latitude)
+    /// rather than a `PROJCS`.
+    pub is_geographic: bool,
+    pub wkt: String,
+}
+
+pub(crate) fn crs(wkt: &str) -> Option<Crs> {
+    let root = parse(wkt)?;
+    let is_geographic = if root.keyword.eq_ignore_ascii_case("GEOGCS") {
+        true
+    } else if root.keyword.eq_ignore_ascii_case("PROJCS") {
+        false
+    } else {
+        return None;
+    };
+    let authority = root.authority();
+    Some(Crs {
+        authority: authority.map(|(name, _)| name.to_owned()),
+        code: authority.and_then(|(_, code)| code.trim().parse().ok()),
+        name: root.args.first().and_then(Value::as_str).map(str::to_owned),
+        is_geographic,
+        wkt: wkt.to_owned(),
+    })
+}
+
+/// The code from the outermost node's `AUTHORITY`, if that's EPSG.
+pub(crate) fn epsg(wkt: &str) -> Option<u32> {
+    match crs(wkt)? {
+        Crs {
+            authority: Some(authority),
+            code,
+            ..
+        } if authority.eq_ignore_ascii_case("EPSG") => code,
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WGS84: &str = r#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]]"#;
+
+    const PSEUDO_MERCATOR: &str = r#"PROJCS["WGS 84 / Pseudo-Mercator",GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]],PROJECTION["Mercator_1SP"],PARAMETER["central_meridian",0],PARAMETER["scale_factor",1],PARAMETER["false_easting",0],PARAMETER["false_northing",0],UNIT["metre",1,AUTHORITY["EPSG","9001"]],AXIS["X",EAST],AXIS["Y",NORTH],EXTENSION["PROJ4","+proj=merc +a=6378137 +b=6378137 +lat_ts=0.0 +lon_0=0.0 +x_0=0.0 +y_0=0 +k=1.0 +units=m +nadgrids=@null +wktext +no_defs"],AUTHORITY["EPSG","3857"]]"#;
+
+    /// What ESRI tools write: no authority clauses anywhere.
+    const ESRI_WGS84: &str = r#"GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]]"#;
+
+    #[test]
+    fn geographic() {
+        assert_eq!(epsg(WGS84), Some(4326));
+    }
+
+    #[test]
+    fn projected() {
+        assert_eq!(epsg(PSEUDO_MERCATOR), Some(3857));
+    }
+
+    #[test]
+    fn no_authority() {
+        assert_eq!(epsg(ESRI_WGS84), None);
+        // only the nested GEOGCS carries an authority, which isn't ours
+        let nested_only = PSEUDO_MERCATOR.replace(r#",AUTHORITY["EPSG","3857"]"#, "");
+        assert_eq!(epsg(&nested_only), None);
+    }
+
+    #[test]
+    fn bad_code() {
+        assert_eq!(epsg(r#"GEOGCS["x",AUTHORITY["EPSG","43x6"]]"#), None);
+        assert_eq!(epsg(r#"GEOGCS["x",AUTHORITY["ESRI","4326"]]"#), None);
+        assert_eq!(epsg(r#"GEOGCS("x",AUTHORITY("EPSG",4326))"#), Some(4326));
+    }
+
+    #[test]
+    fn crs_fields() {
+        let mercator = crs(PSEUDO_MERCATOR).unwrap();
+        assert_eq!(mercator.authority.as_deref(), Some("EPSG"));
+        assert_eq!(mercator.code, Some(3857));
+        assert_eq!(mercator.name.as_deref(), Some("WGS 84 / Pseudo-Mercator"));
+        assert!(!mercator.is_geographic);
+        assert_eq!(mercator.wkt, PSEUDO_MERCATOR);
+
+        let esri = crs(ESRI_WGS84).unwrap();
+        assert_eq!((esri.authority, esri.code), (None, None));
+        assert_eq!(esri.name.as_deref(), Some("GCS_WGS_1984"));
+        assert!(esri.is_geographic);
+
+        assert_eq!(crs(r#"VERT_CS["x"]"#), None);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(parse(r#"GEOGCS["x""#), None);
+        assert_eq!(parse(r#"GEOGCS["x"]]"#), None);
+        assert_eq!(parse(r#"GEOGCS["x",,]"#), None);
+        assert_eq!(
+            parse(r#"UNIT["say ""hi""",1]"#)
+                .unwrap()
+                .args
+                .first()
+                .and_then(Value::as_str),
+            Some(r#"say "hi""#)
+        );
+    }
+
+    #[test]
+    fn too_deep() {
+        let nested = |depth| format!("{}{}", "A[".repeat(depth), "]".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_some());
+        assert_eq!(parse(&nested(MAX_DEPTH + 1)), None);
+        // deep enough to overflow the stack if the depth weren't capped
+        assert_eq!(epsg(&nested(1_000_000)), None);
+    }
+}
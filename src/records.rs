@@ -0,0 +1,86 @@
+//! Reading the bounding boxes `.shp` records keep ahead of their points.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::Result;
+
+/// The `[min_x, min_y, max_x, max_y]` box of every record in `shp`, in
+/// order, as stored in the record itself: only the first few bytes of each
+/// one get read. Points have no stored box, so theirs is the point itself;
+/// null shapes get `NaN`s.
+pub(crate) fn bboxes<R>(shp: &mut R) -> Result<Vec<[f64; 4]>>
+where
+    R: Read + Seek,
+{
+    let mut bboxes = Vec::new();
+    let mut offset = 100u64;
+    shp.seek(SeekFrom::Start(offset))?;
+    loop {
+        let mut header = [0; 8];
+        match shp.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        }
+        let length = i32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        if length < 0 {
+            return Err(io::Error::from(io::ErrorKind::InvalidData).into());
+        }
+
+        let mut shape_type = [0; 4];
+        shp.read_exact(&mut shape_type)?;
+        let bbox = match i32::from_le_bytes(shape_type) {
+            0 => [f64::NAN; 4],
+            // point, pointZ, pointM
+            1 | 11 | 21 => {
+                let [x, y] = read_f64s::<_, 2>(shp)?;
+                [x, y, x, y]
+            }
+            // multipoints, polylines, polygons and multipatches
+            3 | 5 | 8 | 13 | 15 | 18 | 23 | 25 | 28 | 31 => read_f64s::<_, 4>(shp)?,
+            _ => return Err(io::Error::from(io::ErrorKind::InvalidData).into()),
+        };
+        bboxes.push(bbox);
+
+        offset += 8 + 2 * length as u64;
+        shp.seek(SeekFrom::Start(offset))?;
+    }
+    shp.seek(SeekFrom::Start(0))?;
+    Ok(bboxes)
+}
+
+fn read_f64s<R: Read, const N: usize>(reader: &mut R) -> Result<[f64; N]> {
+    let mut values = [0.0; N];
+    for value in &mut values {
+        let mut bytes = [0; 8];
+        reader.read_exact(&mut bytes)?;
+        *value = f64::from_le_bytes(bytes);
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn polylines_and_points() {
+        let line = shapefile::Polyline::new(vec![
+            shapefile::Point::new(1.0, 5.0),
+            shapefile::Point::new(3.0, 2.0),
+        ]);
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[line.clone(), line])
+            .unwrap();
+        assert_eq!(bboxes(&mut shp).unwrap(), vec![[1.0, 2.0, 3.0, 5.0]; 2]);
+
+        let mut shp = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut shp)
+            .write_shapes(&[shapefile::Point::new(4.0, -1.0)])
+            .unwrap();
+        assert_eq!(bboxes(&mut shp).unwrap(), vec![[4.0, -1.0, 4.0, -1.0]]);
+    }
+}
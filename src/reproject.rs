@@ -0,0 +1,213 @@
+//! Reprojecting a `.shp` in place, record by record.
+//!
+//! Only the x/y coordinates change, so every record keeps its length and
+//! the `.shx` still fits; null shapes, z and m values pass through as they
+//! are. The bounding boxes (the header's and each record's) are recomputed
+//! from the transformed points.
+
+use std::io;
+
+use crate::Result;
+
+/// Transforms every point of `shp`, the contents of a `.shp`, with
+/// `transform`.
+pub(crate) fn shp<F>(shp: &mut [u8], mut transform: F) -> Result<()>
+where
+    F: FnMut(f64, f64) -> Result<(f64, f64)>,
+{
+    let mut extent: Option<[f64; 4]> = None;
+    let mut offset = 100;
+    while offset + 8 <= shp.len() {
+        let length = size(i32_be(shp, offset + 4)?)?
+            .checked_mul(2)
+            .ok_or_else(invalid)?;
+        let start = offset + 8;
+        let end = start.checked_add(length).ok_or_else(invalid)?;
+        let record = shp.get_mut(start..end).ok_or_else(eof)?;
+        if let Some(bbox) = record_points(record, &mut transform)? {
+            extend(&mut extent, bbox);
+        }
+        offset = end;
+    }
+    if let Some(extent) = extent {
+        write_bbox(shp.get_mut(36..68).ok_or_else(eof)?, extent);
+    }
+    Ok(())
+}
+
+/// Transforms the points of a single record (its shape type onwards),
+/// returning their extent; `None` for null shapes and shapes without any
+/// points.
+fn record_points<F>(record: &mut [u8], transform: &mut F) -> Result<Option<[f64; 4]>>
+where
+    F: FnMut(f64, f64) -> Result<(f64, f64)>,
+{
+    let (points, count) = match i32_le(record, 0)? {
+        0 => return Ok(None),
+        // point, pointZ, pointM
+        1 | 11 | 21 => (4, 1),
+        // multipoints
+        8 | 18 | 28 => (40, size(i32_le(record, 36)?)?),
+        // polylines and polygons
+        3 | 13 | 23 | 5 | 15 | 25 => (
+            after_parts(size(i32_le(record, 36)?)?, 4)?,
+            size(i32_le(record, 40)?)?,
+        ),
+        // multipatches, which also have a type for each part
+        31 => (
+            after_parts(size(i32_le(record, 36)?)?, 8)?,
+            size(i32_le(record, 40)?)?,
+        ),
+        _ => return Err(invalid()),
+    };
+    // checked up front, so that the offsets below can't overflow and no
+    // point gets transformed in a record that turns out to be cut short
+    let end = count
+        .checked_mul(16)
+        .and_then(|len| len.checked_add(points))
+        .ok_or_else(invalid)?;
+    if end > record.len() {
+        return Err(eof());
+    }
+
+    let mut extent = None;
+    for index in 0..count {
+        let at = points + 16 * index;
+        let (x, y) = transform(f64_le(record, at)?, f64_le(record, at + 8)?)?;
+        record[at..at + 8].copy_from_slice(&x.to_le_bytes());
+        record[at + 8..at + 16].copy_from_slice(&y.to_le_bytes());
+        extend(&mut extent, [x, y, x, y]);
+    }
+    if let (Some(extent), true) = (extent, points > 4) {
+        write_bbox(&mut record[4..36], extent);
+    }
+    Ok(extent)
+}
+
+fn extend(extent: &mut Option<[f64; 4]>, bbox: [f64; 4]) {
+    *extent = Some(match *extent {
+        Some([min_x, min_y, max_x, max_y]) => [
+            min_x.min(bbox[0]),
+            min_y.min(bbox[1]),
+            max_x.max(bbox[2]),
+            max_y.max(bbox[3]),
+        ],
+        None => bbox,
+    });
+}
+
+/// Writes `[min_x, min_y, max_x, max_y]`, which is the order shapefiles
+/// store them in too.
+fn write_bbox(buf: &mut [u8], bbox: [f64; 4]) {
+    for (chunk, value) in buf.chunks_exact_mut(8).zip(bbox) {
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// Where the points start in a poly-something record with `parts` parts,
+/// each taking up `part_len` bytes ahead of the points.
+fn after_parts(parts: usize, part_len: usize) -> Result<usize> {
+    parts
+        .checked_mul(part_len)
+        .and_then(|len| len.checked_add(44))
+        .ok_or_else(invalid)
+}
+
+/// A length or count read from the file, which has to be at least 0.
+fn size(value: i32) -> Result<usize> {
+    usize::try_from(value).map_err(|_| invalid())
+}
+
+fn eof() -> crate::Error {
+    io::Error::from(io::ErrorKind::UnexpectedEof).into()
+}
+
+fn invalid() -> crate::Error {
+    io::Error::from(io::ErrorKind::InvalidData).into()
+}
+
+fn i32_be(buf: &[u8], at: usize) -> Result<i32> {
+    let bytes = buf.get(at..at + 4).ok_or_else(eof)?;
+    Ok(i32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn i32_le(buf: &[u8], at: usize) -> Result<i32> {
+    let bytes = buf.get(at..at + 4).ok_or_else(eof)?;
+    Ok(i32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn f64_le(buf: &[u8], at: usize) -> Result<f64> {
+    let bytes = buf.get(at..at + 8).ok_or_else(eof)?;
+    Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::Shape;
+
+    #[test]
+    fn polylines() {
+        let line = shapefile::Polyline::with_parts(vec![
+            vec![
+                shapefile::Point::new(1.0, 2.0),
+                shapefile::Point::new(3.0, 4.0),
+            ],
+            vec![
+                shapefile::Point::new(-1.0, 0.0),
+                shapefile::Point::new(0.0, 0.0),
+            ],
+        ]);
+        let mut buf = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut buf)
+            .write_shapes(&[line])
+            .unwrap();
+        let mut buf = buf.into_inner();
+        shp(&mut buf, |x, y| Ok((x * 10.0, y + 1.0))).unwrap();
+
+        let reader = shapefile::ShapeReader::new(Cursor::new(&buf)).unwrap();
+        let bbox = reader.header().bbox;
+        assert_eq!(
+            [bbox.min.x, bbox.min.y, bbox.max.x, bbox.max.y],
+            [-10.0, 1.0, 30.0, 5.0]
+        );
+        match &reader.read().unwrap()[..] {
+            [Shape::Polyline(line)] => {
+                assert_eq!(line.parts()[0][1], shapefile::Point::new(30.0, 5.0));
+                assert_eq!(line.bbox().max, shapefile::Point::new(30.0, 5.0));
+            }
+            _ => panic!("expected a single polyline"),
+        }
+    }
+
+    #[test]
+    fn corrupt_records() {
+        let mut buf = Cursor::new(Vec::new());
+        shapefile::ShapeWriter::new(&mut buf)
+            .write_shapes(&[shapefile::Polyline::new(vec![
+                shapefile::Point::new(1.0, 2.0),
+                shapefile::Point::new(3.0, 4.0),
+            ])])
+            .unwrap();
+        let valid = buf.into_inner();
+        let kind = |shp: &mut Vec<u8>| match super::shp(shp, |x, y| Ok((x, y))) {
+            Err(crate::Error::IOError(err)) => err.kind(),
+            _ => panic!("expected an I/O error"),
+        };
+
+        let mut negative_length = valid.clone();
+        negative_length[104..108].copy_from_slice(&(-1i32).to_be_bytes());
+        assert_eq!(kind(&mut negative_length), io::ErrorKind::InvalidData);
+        let mut negative_parts = valid.clone();
+        negative_parts[144..148].copy_from_slice(&(-1i32).to_le_bytes());
+        assert_eq!(kind(&mut negative_parts), io::ErrorKind::InvalidData);
+        let mut too_many_points = valid.clone();
+        too_many_points[148..152].copy_from_slice(&i32::MAX.to_le_bytes());
+        assert_eq!(kind(&mut too_many_points), io::ErrorKind::UnexpectedEof);
+        let mut truncated = valid;
+        truncated.truncate(truncated.len() - 8);
+        assert_eq!(kind(&mut truncated), io::ErrorKind::UnexpectedEof);
+    }
+}
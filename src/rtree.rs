@@ -0,0 +1,63 @@
+//! Indexing shapes by their bounding boxes with `rstar`.
+
+use rstar::{PointDistance, RTreeObject, AABB};
+
+/// An entry of the R-tree built by `Shapefile::build_rtree()`: a shape's
+/// bounding box and its index in the layer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IndexedGeometry {
+    /// The shape's zero-based index, for `shape_at()` and the like.
+    pub index: usize,
+    /// The `[min_x, min_y, max_x, max_y]` bounding box recorded for the
+    /// shape in the `.shp`.
+    pub bbox: [f64; 4],
+}
+
+impl RTreeObject for IndexedGeometry {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        let [min_x, min_y, max_x, max_y] = self.bbox;
+        AABB::from_corners([min_x, min_y], [max_x, max_y])
+    }
+}
+
+/// Distances are to the bounding box, which is zero anywhere inside it.
+impl PointDistance for IndexedGeometry {
+    fn distance_2(&self, point: &[f64; 2]) -> f64 {
+        self.envelope().distance_2(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstar::RTree;
+
+    #[test]
+    fn queries() {
+        let tree = RTree::bulk_load(vec![
+            IndexedGeometry {
+                index: 0,
+                bbox: [0.0, 0.0, 1.0, 1.0],
+            },
+            IndexedGeometry {
+                index: 1,
+                bbox: [5.0, 5.0, 6.0, 6.0],
+            },
+        ]);
+        let nearest = tree.nearest_neighbor(&[4.0, 4.5]).unwrap();
+        assert_eq!(nearest.index, 1);
+        let hits: Vec<_> = tree
+            .locate_in_envelope_intersecting(&AABB::from_corners([0.5, 0.5], [2.0, 2.0]))
+            .map(|entry| entry.index)
+            .collect();
+        assert_eq!(hits, vec![0]);
+        assert_eq!(
+            tree.nearest_neighbor(&[0.5, 0.5])
+                .unwrap()
+                .distance_2(&[0.5, 0.5]),
+            0.0
+        );
+    }
+}
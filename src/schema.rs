@@ -0,0 +1,32 @@
+//! Describing the fields of a DBF.
+
+use dbase::FieldType;
+
+/// One field of a DBF, as declared in its header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: String,
+    pub field_type: FieldType,
+    /// The width of the field in bytes.
+    pub length: u8,
+    /// How many digits of a numeric field come after the decimal point.
+    pub decimal_count: u8,
+}
+
+impl FieldInfo {
+    pub(crate) fn new(field: &dbase::FieldInfo, decimal_count: u8) -> Self {
+        Self {
+            name: field.name().to_owned(),
+            field_type: field.field_type(),
+            length: field.length(),
+            decimal_count,
+        }
+    }
+}
+
+/// The decimal count of the `index`th field, straight from its descriptor,
+/// since `dbase` doesn't expose it. The 32-byte descriptors follow the
+/// 32-byte file header, with the count at offset 17.
+pub(crate) fn decimal_count(dbf: &[u8], index: usize) -> u8 {
+    dbf.get(32 + 32 * index + 17).copied().unwrap_or(0)
+}
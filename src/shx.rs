@@ -0,0 +1,87 @@
+//! Rebuilding a missing (or stale) `.shx` by scanning its `.shp`.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::Result;
+
+/// The contents of the `.shx` that should have come with `shp`: the same
+/// 100-byte header (but for the file length), then the offset and content
+/// length of every record, both in 16-bit words and big-endian.
+///
+/// Only the record headers are read, skipping over the shapes themselves.
+/// A record header cut short by the end of the file ends the index (reading
+/// the last shape then fails as it would have with a real `.shx`).
+pub(crate) fn build<R>(shp: &mut R) -> Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let mut index = vec![0; 100];
+    shp.seek(SeekFrom::Start(0))?;
+    shp.read_exact(&mut index)?;
+
+    let mut offset = 100u64;
+    loop {
+        let mut record = [0; 8];
+        match shp.read_exact(&mut record) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        }
+        let length = i32::from_be_bytes([record[4], record[5], record[6], record[7]]);
+        if length < 0 {
+            break;
+        }
+        index.extend_from_slice(&((offset / 2) as i32).to_be_bytes());
+        index.extend_from_slice(&length.to_be_bytes());
+        offset += 8 + 2 * length as u64;
+        shp.seek(SeekFrom::Start(offset))?;
+    }
+    shp.seek(SeekFrom::Start(0))?;
+
+    let words = (index.len() / 2) as i32;
+    index[24..28].copy_from_slice(&words.to_be_bytes());
+    Ok(index)
+}
+
+/// Whether every record in the `.shx` `index` lies within a `.shp` of
+/// `shp_len` bytes. A stale index, left over from before shapes were
+/// removed, points past the end.
+pub(crate) fn fits(index: &[u8], shp_len: u64) -> bool {
+    let records = match index.get(100..) {
+        Some(records) if records.len() % 8 == 0 => records,
+        _ => return false,
+    };
+    records.chunks_exact(8).all(|record| {
+        let offset = i32::from_be_bytes([record[0], record[1], record[2], record[3]]);
+        let length = i32::from_be_bytes([record[4], record[5], record[6], record[7]]);
+        match (u64::try_from(offset), u64::try_from(length)) {
+            (Ok(offset), Ok(length)) => 2 * offset >= 100 && 2 * offset + 8 + 2 * length <= shp_len,
+            _ => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn matches_written_shx() {
+        let points = [
+            shapefile::Point::new(1.0, 2.0),
+            shapefile::Point::new(3.0, 4.0),
+        ];
+        let (mut shp, mut shx) = (Cursor::new(Vec::new()), Cursor::new(Vec::new()));
+        shapefile::ShapeWriter::with_shx(&mut shp, &mut shx)
+            .write_shapes(&points)
+            .unwrap();
+        assert_eq!(build(&mut shp).unwrap(), *shx.get_ref());
+
+        let len = shp.get_ref().len() as u64;
+        assert!(fits(shx.get_ref(), len));
+        assert!(!fits(shx.get_ref(), len - 1));
+        assert!(!fits(&shx.get_ref()[..99], len));
+    }
+}
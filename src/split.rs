@@ -0,0 +1,188 @@
+//! Joining the volumes of a split zip (`.z01`, `.z02`, …, `.zip`) back
+//! into a single archive.
+//!
+//! Volumes are plain slices of one archive, so gluing them together gets
+//! every member's data in place; but the central directory gives each
+//! member's offset within its own volume, along with the volume's number.
+//! Those are rewritten to offsets into the whole, on volume zero, which is
+//! all the `zip` crate needs. Zip64 archives aren't handled.
+
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::{Error, Result};
+
+const CENTRAL_HEADER: &[u8; 4] = b"PK\x01\x02";
+const END_OF_CENTRAL_DIRECTORY: &[u8; 4] = b"PK\x05\x06";
+const ZIP64_LOCATOR: &[u8; 4] = b"PK\x06\x07";
+/// The fixed part of the end of central directory record.
+const EOCD_LEN: usize = 22;
+/// The most the end of central directory record can be from the end,
+/// since its comment is at most 64 KiB.
+const EOCD_MAX_LEN: u64 = EOCD_LEN as u64 + u16::MAX as u64;
+/// The fixed part of a central directory header.
+const CENTRAL_HEADER_LEN: usize = 46;
+
+/// The archive split into `volumes`, given in order, with the `.zip`
+/// last.
+pub(crate) fn join(volumes: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    let mut starts = Vec::with_capacity(volumes.len());
+    let mut archive = Vec::with_capacity(volumes.iter().map(Vec::len).sum());
+    for volume in volumes {
+        starts.push(archive.len());
+        archive.extend_from_slice(&volume);
+    }
+    if u32::try_from(archive.len()).is_err() {
+        return Err(Error::SplitArchiveUnsupported);
+    }
+    let last = *starts.last().ok_or_else(|| corrupt("no volumes"))?;
+
+    let eocd = (last..=archive.len().saturating_sub(EOCD_LEN))
+        .rev()
+        .find(|&at| is_eocd(&archive[at..]))
+        .ok_or_else(|| corrupt("no end of central directory in the last volume"))?;
+    if eocd >= 20 && archive[eocd - 20..].starts_with(ZIP64_LOCATOR) {
+        return Err(Error::SplitArchiveUnsupported);
+    }
+    let volume_count = u16_at(&archive, eocd + 4) as usize + 1;
+    if volume_count != starts.len() {
+        return Err(corrupt(&format!(
+            "the archive has {} volumes, not {}",
+            volume_count,
+            starts.len()
+        )));
+    }
+    let directory_volume = u16_at(&archive, eocd + 6) as usize;
+    let entries = u16_at(&archive, eocd + 10);
+    let directory_offset = u32_at(&archive, eocd + 16);
+    if entries == u16::MAX || directory_offset == u32::MAX {
+        return Err(Error::SplitArchiveUnsupported);
+    }
+    let directory = starts
+        .get(directory_volume)
+        .ok_or_else(|| corrupt("central directory on a missing volume"))?
+        + directory_offset as usize;
+
+    let mut at = directory;
+    for _ in 0..entries {
+        if at + CENTRAL_HEADER_LEN > eocd || !archive[at..].starts_with(CENTRAL_HEADER) {
+            return Err(corrupt("invalid central directory header"));
+        }
+        let volume = u16_at(&archive, at + 34);
+        let offset = u32_at(&archive, at + 42);
+        if volume == u16::MAX || offset == u32::MAX {
+            return Err(Error::SplitArchiveUnsupported);
+        }
+        let start = starts
+            .get(volume as usize)
+            .ok_or_else(|| corrupt("member on a missing volume"))?;
+        set_u16(&mut archive, at + 34, 0);
+        set_u32(&mut archive, at + 42, (start + offset as usize) as u32);
+        at += CENTRAL_HEADER_LEN
+            + u16_at(&archive, at + 28) as usize
+            + u16_at(&archive, at + 30) as usize
+            + u16_at(&archive, at + 32) as usize;
+    }
+
+    set_u16(&mut archive, eocd + 4, 0);
+    set_u16(&mut archive, eocd + 6, 0);
+    set_u16(&mut archive, eocd + 8, entries);
+    set_u32(&mut archive, eocd + 16, directory as u32);
+    Ok(archive)
+}
+
+/// Whether `archive` is the last volume of a split archive (and not a
+/// whole one), going by the volume number of its end of central directory
+/// record. Anything without such a record isn't, as far as this goes.
+/// `archive` is left at its start.
+pub(crate) fn is_last_volume<R>(archive: &mut R) -> io::Result<bool>
+where
+    R: Read + Seek,
+{
+    let len = archive.seek(SeekFrom::End(0))?;
+    archive.seek(SeekFrom::Start(len.saturating_sub(EOCD_MAX_LEN)))?;
+    let mut tail = Vec::new();
+    archive.read_to_end(&mut tail)?;
+    archive.seek(SeekFrom::Start(0))?;
+    Ok((0..=tail.len().saturating_sub(EOCD_LEN))
+        .rev()
+        .find(|&at| is_eocd(&tail[at..]))
+        .map_or(false, |eocd| u16_at(&tail, eocd + 4) != 0))
+}
+
+fn is_eocd(buf: &[u8]) -> bool {
+    buf.len() >= EOCD_LEN && buf.starts_with(END_OF_CENTRAL_DIRECTORY)
+}
+
+fn corrupt(message: &str) -> Error {
+    Error::CorruptArchive(message.to_owned())
+}
+
+fn u16_at(buf: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([buf[at], buf[at + 1]])
+}
+
+fn u32_at(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+fn set_u16(buf: &mut [u8], at: usize, value: u16) {
+    buf[at..at + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+fn set_u32(buf: &mut [u8], at: usize, value: u32) {
+    buf[at..at + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+    use super::*;
+
+    fn archive() -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("a.txt", FileOptions::default()).unwrap();
+        writer.write_all(b"hello").unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn lone_volume() {
+        let whole = archive();
+        let mut cursor = Cursor::new(&whole);
+        assert!(!is_last_volume(&mut cursor).unwrap());
+        assert_eq!(cursor.position(), 0);
+        assert!(!is_last_volume(&mut Cursor::new(b"not a zip")).unwrap());
+
+        // a whole archive is a split one of a single volume
+        let joined = join(vec![whole.clone()]).unwrap();
+        let mut joined = ZipArchive::new(Cursor::new(joined)).unwrap();
+        let mut contents = String::new();
+        joined
+            .by_name("a.txt")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "hello");
+    }
+
+    #[test]
+    fn mismatched_volumes() {
+        let whole = archive();
+        assert!(matches!(
+            join(vec![whole.clone(), whole.clone()]),
+            Err(Error::CorruptArchive(_))
+        ));
+        assert!(matches!(join(vec![]), Err(Error::CorruptArchive(_))));
+
+        // the second of two volumes, given on its own
+        let mut last = whole;
+        let eocd = last.len() - EOCD_LEN;
+        set_u16(&mut last, eocd + 4, 1);
+        set_u16(&mut last, eocd + 6, 1);
+        assert!(is_last_volume(&mut Cursor::new(&last)).unwrap());
+        assert!(matches!(join(vec![last]), Err(Error::CorruptArchive(_))));
+    }
+}
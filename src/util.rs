@@ -0,0 +1,96 @@
+//! Small helpers for working with shapes and DBF values.
+
+use dbase::{Date, DateTime};
+use shapefile::{PointZ, Shape, NO_DATA};
+
+/// Whether `shape` has z values: whether it's one of the `Z` types or a
+/// multipatch.
+pub fn has_z(shape: &Shape) -> bool {
+    matches!(
+        shape,
+        Shape::PointZ(_)
+            | Shape::PolylineZ(_)
+            | Shape::PolygonZ(_)
+            | Shape::MultipointZ(_)
+            | Shape::Multipatch(_)
+    )
+}
+
+/// Whether `shape` has m values. The `M` types always do, but the `Z` types
+/// and multipatches only have room for them, so they count when any of their
+/// points has an m that isn't `NO_DATA`.
+pub fn has_m(shape: &Shape) -> bool {
+    match shape {
+        Shape::PointM(_) | Shape::PolylineM(_) | Shape::PolygonM(_) | Shape::MultipointM(_) => true,
+        Shape::PointZ(point) => m_used(point),
+        Shape::PolylineZ(line) => line.parts().iter().flatten().any(m_used),
+        Shape::PolygonZ(polygon) => polygon
+            .rings()
+            .iter()
+            .flat_map(|ring| ring.points())
+            .any(m_used),
+        Shape::MultipointZ(points) => points.points().iter().any(m_used),
+        Shape::Multipatch(multipatch) => multipatch
+            .patches()
+            .iter()
+            .flat_map(|patch| patch.points())
+            .any(m_used),
+        _ => false,
+    }
+}
+
+fn m_used(point: &PointZ) -> bool {
+    point.m > NO_DATA
+}
+
+/// `date` as ISO 8601, like `2023-01-31`.
+pub(crate) fn iso_date(date: &Date) -> String {
+    format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day())
+}
+
+/// `datetime` as ISO 8601, like `2023-01-31T12:00:00`, without a time zone
+/// since DBF doesn't have one.
+pub(crate) fn iso_datetime(datetime: &DateTime) -> String {
+    let time = datetime.time();
+    format!(
+        "{}T{:02}:{:02}:{:02}",
+        iso_date(&datetime.date()),
+        time.hours(),
+        time.minutes(),
+        time.seconds()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shapefile::{Point, PointM, PolylineZ};
+
+    #[test]
+    fn flags() {
+        let plain = Shape::Point(Point::new(1.0, 2.0));
+        assert!(!has_z(&plain) && !has_m(&plain));
+
+        let measured = Shape::PointM(PointM::new(1.0, 2.0, NO_DATA));
+        assert!(!has_z(&measured) && has_m(&measured));
+
+        let line = |m| {
+            Shape::PolylineZ(PolylineZ::new(vec![
+                PointZ::new(0.0, 0.0, 1.0, NO_DATA),
+                PointZ::new(1.0, 1.0, 1.0, m),
+            ]))
+        };
+        assert!(has_z(&line(NO_DATA)) && !has_m(&line(NO_DATA)));
+        assert!(has_z(&line(5.0)) && has_m(&line(5.0)));
+
+        assert!(!has_z(&Shape::NullShape) && !has_m(&Shape::NullShape));
+    }
+
+    #[test]
+    fn iso_dates() {
+        let date = Date::new(2, 3, 201);
+        assert_eq!(iso_date(&date), "0201-03-02");
+        let datetime = DateTime::new(date, dbase::Time::new(4, 5, 6));
+        assert_eq!(iso_datetime(&datetime), "0201-03-02T04:05:06");
+    }
+}
@@ -0,0 +1,154 @@
+//! Finding polygon rings that spatial operations will trip over.
+
+use geo::{line_intersection::LineIntersection, sweep::Intersections, Area};
+use geo_types::{Coord, LineString, Polygon};
+use shapefile::{record::traits::HasXY, PolygonRing};
+
+use crate::{Result, Shape};
+
+/// Something wrong with one ring of a polygon.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeometryIssue {
+    /// The index of the shape (and its record).
+    pub index: usize,
+    /// The index of the ring within the shape, holes included.
+    pub ring: usize,
+    pub kind: GeometryIssueKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeometryIssueKind {
+    /// The ring's last point isn't its first.
+    UnclosedRing,
+    /// The ring encloses no area, having too few points or all of them on
+    /// a line.
+    ZeroArea,
+    /// Two of the ring's edges cross or overlap, other than neighbours
+    /// meeting at the point they share.
+    SelfIntersection,
+}
+
+/// The issues with every polygon in `shapes`, in order; other shapes don't
+/// have rings to get wrong.
+pub(crate) fn issues<I>(shapes: I, self_intersections: bool) -> Result<Vec<GeometryIssue>>
+where
+    I: Iterator<Item = Result<Shape>>,
+{
+    let mut issues = Vec::new();
+    for (index, shape) in shapes.enumerate() {
+        match shape? {
+            Shape::Polygon(polygon) => {
+                check(&mut issues, index, polygon.rings(), self_intersections)
+            }
+            Shape::PolygonM(polygon) => {
+                check(&mut issues, index, polygon.rings(), self_intersections)
+            }
+            Shape::PolygonZ(polygon) => {
+                check(&mut issues, index, polygon.rings(), self_intersections)
+            }
+            _ => {}
+        }
+    }
+    Ok(issues)
+}
+
+fn check<P: HasXY>(
+    issues: &mut Vec<GeometryIssue>,
+    index: usize,
+    rings: &[PolygonRing<P>],
+    self_intersections: bool,
+) {
+    for (ring, points) in rings.iter().enumerate() {
+        let mut issue = |kind| issues.push(GeometryIssue { index, ring, kind });
+        let points = points.points();
+        let closed = match (points.first(), points.last()) {
+            (Some(first), Some(last)) => first.x() == last.x() && first.y() == last.y(),
+            _ => false,
+        };
+        if !closed {
+            issue(GeometryIssueKind::UnclosedRing);
+        }
+
+        // `Polygon::new` closes the ring, so that the rest is checked as
+        // if it had been
+        let line: LineString<f64> = points
+            .iter()
+            .map(|point| Coord {
+                x: point.x(),
+                y: point.y(),
+            })
+            .collect();
+        let polygon = Polygon::new(line, vec![]);
+        if polygon.unsigned_area() == 0.0 {
+            issue(GeometryIssueKind::ZeroArea);
+        }
+        if self_intersections && crosses_itself(polygon.exterior()) {
+            issue(GeometryIssueKind::SelfIntersection);
+        }
+    }
+}
+
+/// Whether any two edges of the closed `ring` cross or overlap. Edges
+/// meeting at an end (as neighbours always do) don't count.
+fn crosses_itself(ring: &LineString<f64>) -> bool {
+    Intersections::from_iter(ring.lines()).any(|(_, _, intersection)| {
+        matches!(
+            intersection,
+            LineIntersection::SinglePoint {
+                is_proper: true,
+                ..
+            } | LineIntersection::Collinear { .. }
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shapefile::Point;
+
+    /// A polygon with a single ring made of `points`, as they are:
+    /// `Polygon::with_rings` would close it.
+    fn polygon(points: &[(f64, f64)]) -> Result<Shape> {
+        let points = points.iter().map(|&(x, y)| Point::new(x, y)).collect();
+        Ok(Shape::Polygon(shapefile::Polyline::new(points).into()))
+    }
+
+    #[test]
+    fn rings() {
+        let square = [(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)];
+        let bowtie = [(0.0, 0.0), (2.0, 2.0), (2.0, 0.0), (0.0, 1.0), (0.0, 0.0)];
+        let flat = [(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (0.0, 0.0)];
+        let shapes = || {
+            vec![
+                polygon(&square),
+                polygon(&bowtie),
+                polygon(&flat),
+                polygon(&square[..4]),
+                Ok(Shape::Point(Point::new(0.0, 0.0))),
+            ]
+            .into_iter()
+        };
+        let issue = |index, kind| GeometryIssue {
+            index,
+            ring: 0,
+            kind,
+        };
+        assert_eq!(
+            issues(shapes(), false).unwrap(),
+            vec![
+                issue(2, GeometryIssueKind::ZeroArea),
+                issue(3, GeometryIssueKind::UnclosedRing),
+            ]
+        );
+        assert_eq!(
+            issues(shapes(), true).unwrap(),
+            vec![
+                issue(1, GeometryIssueKind::SelfIntersection),
+                issue(2, GeometryIssueKind::ZeroArea),
+                issue(2, GeometryIssueKind::SelfIntersection),
+                issue(3, GeometryIssueKind::UnclosedRing),
+            ]
+        );
+    }
+}
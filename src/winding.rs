@@ -0,0 +1,103 @@
+//! Turning shapefile polygons into OGC polygons, whose outer rings run
+//! counter-clockwise and holes clockwise (the opposite of shapefiles).
+
+use std::cmp::Ordering;
+
+use geo::{
+    orient::{Direction, Orient},
+    Area, Contains,
+};
+use geo_types::{Coord, LineString, MultiPolygon, Point, Polygon};
+use shapefile::{record::traits::HasXY, PolygonRing};
+
+/// The polygons made up by `rings`, with each hole given to the smallest
+/// outer ring around it, wherever it comes in the record. Shapefiles don't
+/// promise that holes follow their outer ring, so going by the order (as
+/// the `geo-types` conversion does) can put them in the wrong polygon.
+/// Holes that aren't inside any outer ring become polygons of their own.
+pub(crate) fn multi_polygon<P: HasXY>(rings: &[PolygonRing<P>]) -> MultiPolygon<f64> {
+    let mut polygons = Vec::new();
+    let mut holes = Vec::new();
+    for ring in rings {
+        match ring {
+            PolygonRing::Outer(points) => polygons.push(Polygon::new(line(points), vec![])),
+            PolygonRing::Inner(points) => holes.push(line(points)),
+        }
+    }
+
+    let areas: Vec<f64> = polygons.iter().map(Polygon::unsigned_area).collect();
+    for hole in holes {
+        let enclosing = (0..areas.len())
+            .filter(|&index| {
+                hole.points()
+                    .any(|point: Point<f64>| polygons[index].contains(&point))
+            })
+            .min_by(|&a, &b| areas[a].partial_cmp(&areas[b]).unwrap_or(Ordering::Equal));
+        match enclosing {
+            Some(index) => polygons[index].interiors_push(hole),
+            None => polygons.push(Polygon::new(hole, vec![])),
+        }
+    }
+
+    MultiPolygon(polygons).orient(Direction::Default)
+}
+
+fn line<P: HasXY>(points: &[P]) -> LineString<f64> {
+    points
+        .iter()
+        .map(|point| Coord {
+            x: point.x(),
+            y: point.y(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::winding_order::{Winding, WindingOrder};
+    use shapefile::Point;
+
+    fn square(x: f64, y: f64, size: f64) -> Vec<Point> {
+        // clockwise, as shapefiles have their outer rings
+        vec![
+            Point::new(x, y),
+            Point::new(x, y + size),
+            Point::new(x + size, y + size),
+            Point::new(x + size, y),
+            Point::new(x, y),
+        ]
+    }
+
+    #[test]
+    fn holes_find_their_ring() {
+        let hole = |x, y| {
+            let mut ring = square(x, y, 1.0);
+            ring.reverse();
+            PolygonRing::Inner(ring)
+        };
+        // both outer rings come first, then the holes in the other order
+        let rings = [
+            PolygonRing::Outer(square(0.0, 0.0, 4.0)),
+            PolygonRing::Outer(square(10.0, 0.0, 4.0)),
+            hole(11.0, 1.0),
+            hole(1.0, 1.0),
+            hole(20.0, 20.0),
+            hole(30.0, 30.0),
+        ];
+        let polygons = multi_polygon(&rings).0;
+        assert_eq!(polygons.len(), 4);
+        assert_eq!(polygons[0].interiors()[0].0[0].x, 1.0);
+        assert_eq!(polygons[1].interiors()[0].0[0].x, 11.0);
+        assert!(polygons[2].interiors().is_empty());
+        for polygon in &polygons {
+            assert_eq!(
+                polygon.exterior().winding_order(),
+                Some(WindingOrder::CounterClockwise)
+            );
+            for hole in polygon.interiors() {
+                assert_eq!(hole.winding_order(), Some(WindingOrder::Clockwise));
+            }
+        }
+    }
+}
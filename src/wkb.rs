@@ -0,0 +1,166 @@
+//! Encoding geometries as Well-Known Binary.
+//!
+//! Everything is written little-endian and two-dimensional, since that's
+//! what the `geo-types` conversion leaves us with.
+
+use geo_types::{Geometry, LineString, Point, Polygon};
+
+const POINT: u32 = 1;
+const LINE_STRING: u32 = 2;
+const POLYGON: u32 = 3;
+const MULTI_POINT: u32 = 4;
+const MULTI_LINE_STRING: u32 = 5;
+const MULTI_POLYGON: u32 = 6;
+const GEOMETRY_COLLECTION: u32 = 7;
+
+/// The WKB for `geometry`.
+///
+/// Shapefile polylines and polygons always convert to their multi-part
+/// `geo-types` equivalents, so single-part ones are written as a plain
+/// `LineString`/`Polygon` instead. Holes stay with the ring they belong to,
+/// so an outer ring with holes is one `Polygon`, not a `MultiPolygon`.
+pub(crate) fn geometry(geometry: &Geometry<f64>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_geometry(&mut buf, geometry);
+    buf
+}
+
+fn write_geometry(buf: &mut Vec<u8>, geometry: &Geometry<f64>) {
+    match geometry {
+        Geometry::Point(point) => write_point(buf, point),
+        Geometry::Line(line) => {
+            write_line_string(buf, &LineString::from(vec![line.start, line.end]))
+        }
+        Geometry::LineString(line) => write_line_string(buf, line),
+        Geometry::Polygon(polygon) => write_polygon(buf, polygon),
+        Geometry::MultiPoint(points) => {
+            write_header(buf, MULTI_POINT, points.0.len());
+            for point in points {
+                write_point(buf, point);
+            }
+        }
+        Geometry::MultiLineString(lines) => match &lines.0[..] {
+            [line] => write_line_string(buf, line),
+            lines => {
+                write_header(buf, MULTI_LINE_STRING, lines.len());
+                for line in lines {
+                    write_line_string(buf, line);
+                }
+            }
+        },
+        Geometry::MultiPolygon(polygons) => match &polygons.0[..] {
+            [polygon] => write_polygon(buf, polygon),
+            polygons => {
+                write_header(buf, MULTI_POLYGON, polygons.len());
+                for polygon in polygons {
+                    write_polygon(buf, polygon);
+                }
+            }
+        },
+        Geometry::GeometryCollection(geometries) => {
+            write_header(buf, GEOMETRY_COLLECTION, geometries.0.len());
+            for geometry in geometries {
+                write_geometry(buf, geometry);
+            }
+        }
+        Geometry::Rect(rect) => write_polygon(buf, &rect.to_polygon()),
+        Geometry::Triangle(triangle) => write_polygon(buf, &triangle.to_polygon()),
+    }
+}
+
+/// The byte order marker and type code, followed by the number of whatever
+/// the geometry is made of.
+fn write_header(buf: &mut Vec<u8>, type_code: u32, len: usize) {
+    buf.push(1);
+    buf.extend_from_slice(&type_code.to_le_bytes());
+    write_len(buf, len);
+}
+
+fn write_len(buf: &mut Vec<u8>, len: usize) {
+    buf.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn write_point(buf: &mut Vec<u8>, point: &Point<f64>) {
+    buf.push(1);
+    buf.extend_from_slice(&POINT.to_le_bytes());
+    buf.extend_from_slice(&point.x().to_le_bytes());
+    buf.extend_from_slice(&point.y().to_le_bytes());
+}
+
+fn write_line_string(buf: &mut Vec<u8>, line: &LineString<f64>) {
+    write_header(buf, LINE_STRING, line.0.len());
+    write_coords(buf, line);
+}
+
+fn write_polygon(buf: &mut Vec<u8>, polygon: &Polygon<f64>) {
+    write_header(buf, POLYGON, 1 + polygon.interiors().len());
+    for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+        write_len(buf, ring.0.len());
+        write_coords(buf, ring);
+    }
+}
+
+fn write_coords(buf: &mut Vec<u8>, line: &LineString<f64>) {
+    for coord in &line.0 {
+        buf.extend_from_slice(&coord.x.to_le_bytes());
+        buf.extend_from_slice(&coord.y.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{line_string, point, polygon, MultiLineString, MultiPolygon};
+
+    /// The type code of a WKB geometry, checking it's little-endian.
+    fn type_code(wkb: &[u8]) -> u32 {
+        assert_eq!(wkb[0], 1);
+        u32::from_le_bytes(wkb[1..5].try_into().unwrap())
+    }
+
+    #[test]
+    fn point() {
+        let wkb = geometry(&Geometry::Point(point!(x: 1.0, y: 2.0)));
+        let mut expected = vec![1, 1, 0, 0, 0];
+        expected.extend_from_slice(&1.0f64.to_le_bytes());
+        expected.extend_from_slice(&2.0f64.to_le_bytes());
+        assert_eq!(wkb, expected);
+    }
+
+    #[test]
+    fn polygons() {
+        let holed = polygon!(
+            exterior: [(x: 0.0, y: 0.0), (x: 4.0, y: 0.0), (x: 4.0, y: 4.0), (x: 0.0, y: 0.0)],
+            interiors: [[(x: 1.0, y: 1.0), (x: 2.0, y: 1.0), (x: 2.0, y: 2.0), (x: 1.0, y: 1.0)]],
+        );
+        let wkb = geometry(&Geometry::MultiPolygon(MultiPolygon(vec![holed.clone()])));
+        assert_eq!(type_code(&wkb), POLYGON);
+        // two rings of four points each
+        assert_eq!(&wkb[5..9], &2u32.to_le_bytes());
+        assert_eq!(wkb.len(), 9 + 2 * (4 + 4 * 16));
+
+        let wkb = geometry(&Geometry::MultiPolygon(MultiPolygon(vec![
+            holed.clone(),
+            holed,
+        ])));
+        assert_eq!(type_code(&wkb), MULTI_POLYGON);
+        assert_eq!(&wkb[5..9], &2u32.to_le_bytes());
+        assert_eq!(type_code(&wkb[9..]), POLYGON);
+    }
+
+    #[test]
+    fn lines() {
+        let line = line_string![(x: 0.0, y: 0.0), (x: 1.0, y: 1.0)];
+        let single = geometry(&Geometry::MultiLineString(MultiLineString(vec![
+            line.clone()
+        ])));
+        assert_eq!(type_code(&single), LINE_STRING);
+        assert_eq!(single, geometry(&Geometry::LineString(line.clone())));
+
+        let double = geometry(&Geometry::MultiLineString(MultiLineString(vec![
+            line.clone(),
+            line,
+        ])));
+        assert_eq!(type_code(&double), MULTI_LINE_STRING);
+    }
+}
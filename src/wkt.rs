@@ -0,0 +1,164 @@
+//! Encoding geometries as Well-Known Text.
+//!
+//! Like the WKB, this is two-dimensional, since that's what the `geo-types`
+//! conversion leaves us with.
+
+use std::fmt::Write;
+
+use geo_types::{Coord, Geometry, LineString, Polygon};
+
+/// The WKT for `geometry`, with single-part polylines and polygons written
+/// as a plain `LINESTRING`/`POLYGON`, as in the WKB.
+pub(crate) fn geometry(geometry: &Geometry<f64>) -> String {
+    let mut buf = String::new();
+    write_geometry(&mut buf, geometry);
+    buf
+}
+
+/// The WKT for a null shape, which has no geometry at all.
+pub(crate) const NULL: &str = "GEOMETRYCOLLECTION EMPTY";
+
+fn write_geometry(buf: &mut String, geometry: &Geometry<f64>) {
+    match geometry {
+        Geometry::Point(point) => {
+            buf.push_str("POINT (");
+            write_coord(buf, &point.0);
+            buf.push(')');
+        }
+        Geometry::Line(line) => {
+            write_line_string(buf, &LineString::from(vec![line.start, line.end]))
+        }
+        Geometry::LineString(line) => write_line_string(buf, line),
+        Geometry::Polygon(polygon) => write_polygon(buf, polygon),
+        Geometry::MultiPoint(points) => {
+            buf.push_str("MULTIPOINT ");
+            write_list(buf, &points.0, |buf, point| {
+                buf.push('(');
+                write_coord(buf, &point.0);
+                buf.push(')');
+            });
+        }
+        Geometry::MultiLineString(lines) => match &lines.0[..] {
+            [line] => write_line_string(buf, line),
+            lines => {
+                buf.push_str("MULTILINESTRING ");
+                write_list(buf, lines, |buf, line| write_coords(buf, &line.0));
+            }
+        },
+        Geometry::MultiPolygon(polygons) => match &polygons.0[..] {
+            [polygon] => write_polygon(buf, polygon),
+            polygons => {
+                buf.push_str("MULTIPOLYGON ");
+                write_list(buf, polygons, write_rings);
+            }
+        },
+        Geometry::GeometryCollection(geometries) => {
+            buf.push_str("GEOMETRYCOLLECTION ");
+            write_list(buf, &geometries.0, write_geometry);
+        }
+        Geometry::Rect(rect) => write_polygon(buf, &rect.to_polygon()),
+        Geometry::Triangle(triangle) => write_polygon(buf, &triangle.to_polygon()),
+    }
+}
+
+/// `(a, b, c)` with each item written by `write_item`, or `EMPTY` when there
+/// aren't any.
+fn write_list<T>(buf: &mut String, items: &[T], mut write_item: impl FnMut(&mut String, &T)) {
+    if items.is_empty() {
+        buf.push_str("EMPTY");
+        return;
+    }
+    buf.push('(');
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            buf.push_str(", ");
+        }
+        write_item(buf, item);
+    }
+    buf.push(')');
+}
+
+fn write_line_string(buf: &mut String, line: &LineString<f64>) {
+    buf.push_str("LINESTRING ");
+    write_coords(buf, &line.0);
+}
+
+fn write_polygon(buf: &mut String, polygon: &Polygon<f64>) {
+    buf.push_str("POLYGON ");
+    write_rings(buf, polygon);
+}
+
+/// The exterior ring followed by the holes.
+fn write_rings(buf: &mut String, polygon: &Polygon<f64>) {
+    if polygon.exterior().0.is_empty() {
+        buf.push_str("EMPTY");
+        return;
+    }
+    let rings: Vec<_> = std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .collect();
+    write_list(buf, &rings, |buf, ring| write_coords(buf, &ring.0));
+}
+
+fn write_coords(buf: &mut String, coords: &[Coord<f64>]) {
+    write_list(buf, coords, write_coord);
+}
+
+fn write_coord(buf: &mut String, coord: &Coord<f64>) {
+    // writing to a `String` can't fail
+    let _ = write!(buf, "{} {}", coord.x, coord.y);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo_types::{line_string, point, polygon, MultiLineString, MultiPolygon};
+
+    #[test]
+    fn point() {
+        assert_eq!(
+            geometry(&Geometry::Point(point!(x: 1.0, y: -2.5))),
+            "POINT (1 -2.5)"
+        );
+    }
+
+    #[test]
+    fn polygons() {
+        let holed = polygon!(
+            exterior: [(x: 0.0, y: 0.0), (x: 4.0, y: 0.0), (x: 4.0, y: 4.0), (x: 0.0, y: 0.0)],
+            interiors: [[(x: 1.0, y: 1.0), (x: 2.0, y: 1.0), (x: 2.0, y: 2.0), (x: 1.0, y: 1.0)]],
+        );
+        let rings = "((0 0, 4 0, 4 4, 0 0), (1 1, 2 1, 2 2, 1 1))";
+        assert_eq!(
+            geometry(&Geometry::MultiPolygon(MultiPolygon(vec![holed.clone()]))),
+            format!("POLYGON {}", rings)
+        );
+        assert_eq!(
+            geometry(&Geometry::MultiPolygon(MultiPolygon(vec![
+                holed.clone(),
+                holed
+            ]))),
+            format!("MULTIPOLYGON ({}, {})", rings, rings)
+        );
+        assert_eq!(
+            geometry(&Geometry::MultiPolygon(MultiPolygon(vec![]))),
+            "MULTIPOLYGON EMPTY"
+        );
+    }
+
+    #[test]
+    fn lines() {
+        let line = line_string![(x: 0.0, y: 0.0), (x: 1.0, y: 1.0)];
+        assert_eq!(
+            geometry(&Geometry::MultiLineString(MultiLineString(vec![
+                line.clone(),
+                line
+            ]))),
+            "MULTILINESTRING ((0 0, 1 1), (0 0, 1 1))"
+        );
+        assert_eq!(
+            geometry(&Geometry::MultiLineString(MultiLineString(vec![]))),
+            "MULTILINESTRING EMPTY"
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Packing the output of a `shapefile::Writer` into a zip archive.
+
+use std::io::{Seek, Write};
+
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::Result;
+
+/// Builds a zip archive holding a single shapefile.
+///
+/// The components are written by `shapefile`'s own writers (into in-memory
+/// buffers, say), then handed over as bytes; they get stored as
+/// `<name>.shp`, `<name>.shx`, `<name>.dbf` and, when there's a projection,
+/// `<name>.prj`.
+pub struct ZippedShapefileWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    name: String,
+    projection: Option<String>,
+}
+
+impl<W> ZippedShapefileWriter<W>
+where
+    W: Write + Seek,
+{
+    /// A writer whose members are named `shapefile.*`.
+    pub fn new(dest: W) -> Self {
+        Self {
+            zip: ZipWriter::new(dest),
+            name: "shapefile".to_owned(),
+            projection: None,
+        }
+    }
+
+    /// Names the members `<name>.shp` and so on; this can include
+    /// directories, as in `data/parcels`.
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Adds a `.prj` member holding this WKT.
+    pub fn with_projection<S: Into<String>>(mut self, wkt: S) -> Self {
+        self.projection = Some(wkt.into());
+        self
+    }
+
+    /// Writes the contents of the `.shp`, `.shx` and `.dbf` (and the `.prj`,
+    /// if any) into the archive.
+    pub fn write(&mut self, shp: &[u8], shx: &[u8], dbf: &[u8]) -> Result<()> {
+        self.write_member("shp", shp)?;
+        self.write_member("shx", shx)?;
+        self.write_member("dbf", dbf)?;
+        if let Some(wkt) = self.projection.clone() {
+            self.write_member("prj", wkt.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write_member(&mut self, extension: &str, contents: &[u8]) -> Result<()> {
+        let name = format!("{}.{}", self.name, extension);
+        self.zip.start_file(name, FileOptions::default())?;
+        self.zip.write_all(contents)?;
+        Ok(())
+    }
+
+    /// Writes the zip central directory, returning the destination.
+    pub fn finish(mut self) -> Result<W> {
+        Ok(self.zip.finish()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{FieldValue, Shape, ZippedShapefile};
+
+    #[test]
+    fn round_trip() {
+        let (mut shp, mut shx, mut dbf) = (
+            Cursor::new(Vec::new()),
+            Cursor::new(Vec::new()),
+            Cursor::new(Vec::new()),
+        );
+        let table = dbase::TableWriterBuilder::new()
+            .add_character_field("NAME".try_into().unwrap(), 16)
+            .build_with_dest(&mut dbf);
+        let mut writer =
+            shapefile::Writer::new(shapefile::ShapeWriter::with_shx(&mut shp, &mut shx), table);
+        let mut record = dbase::Record::default();
+        record.insert(
+            "NAME".to_owned(),
+            FieldValue::Character(Some("origin".to_owned())),
+        );
+        writer
+            .write_shape_and_record(&shapefile::Point::new(0.0, 0.0), &record)
+            .unwrap();
+        drop(writer);
+
+        let wkt = r#"GEOGCS["WGS 84",AUTHORITY["EPSG","4326"]]"#;
+        let mut zip = ZippedShapefileWriter::new(Cursor::new(Vec::new()))
+            .with_name("data/origin")
+            .with_projection(wkt);
+        zip.write(shp.get_ref(), shx.get_ref(), dbf.get_ref())
+            .unwrap();
+        let mut archive = zip.finish().unwrap();
+        archive.set_position(0);
+
+        let mut zipped = ZippedShapefile::new(archive).unwrap();
+        assert_eq!(zipped.layers(), vec!["data/origin"]);
+        assert_eq!(zipped.projection().unwrap(), Some(wkt));
+        assert_eq!(zipped.shape_count().unwrap(), 1);
+        let (shape, record) = zipped.reader().unwrap().read().unwrap().remove(0);
+        assert!(matches!(shape, Shape::Point(p) if p == shapefile::Point::new(0.0, 0.0)));
+        assert_eq!(
+            record.get("NAME"),
+            Some(&FieldValue::Character(Some("origin".to_owned())))
+        );
+    }
+}